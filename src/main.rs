@@ -1,11 +1,17 @@
 use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::ecs::system::SystemParam;
 use bevy::input::common_conditions::input_just_pressed;
 use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
 use bevy::render::view::RenderLayers;
+use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
 use bevy::window::{Cursor, PresentMode, WindowMode};
 use bevy_rand::prelude::*;
 use devcaders;
-use rand::Rng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
 use std::env;
 use std::f32::consts::PI;
 
@@ -23,6 +29,11 @@ struct DebugSettings {
 
 fn main() {
     let devcade: bool = env::var_os("DEVCADE_PATH").is_some();
+    // Set VOID_BREAK_SEED to replay a run
+    let seed: u64 = env::var("VOID_BREAK_SEED")
+        .ok()
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or_else(rand::random);
 
     let mut game = App::new();
 
@@ -45,21 +56,38 @@ fn main() {
         }),
         ..Default::default()
     }))
-    .add_plugins(EntropyPlugin::<WyRand>::default())
+    .add_plugins(EntropyPlugin::<WyRand>::with_seed(seed.to_le_bytes()))
     .add_plugins(FrameTimeDiagnosticsPlugin)
     .init_state::<GameState>()
     .add_event::<CollisionEvent>()
+    .add_event::<SensorEvent>()
+    .add_event::<DamageDealt>()
+    .add_event::<Died>()
     .add_systems(Startup, (spawn_core, spawn_asteroids))
     .add_systems(Startup, (setup_fps_counter, setup_ui).after(spawn_core))
     .add_systems(Startup, setup_tutorials)
     .add_systems(Update, (fps_text_update_system, fps_counter_showhide))
     .add_systems(
         Update,
-        player_controller
+        (player_controller, steer_enemies)
             .before(movement)
             .run_if(in_state(GameState::Play)),
     )
     .add_systems(Update, movement.run_if(in_state(GameState::Play)))
+    .add_systems(
+        Update,
+        (
+            switch_weapons.before(fire_weapons),
+            cool_racked_weapons.before(switch_weapons),
+            fire_weapons.after(player_controller).after(steer_enemies),
+            steer_homing.before(movement),
+            launch_drones,
+            run_waves,
+            advance_boss_phases.before(steer_enemies),
+            update_charge_glows.after(fire_weapons),
+        )
+            .run_if(in_state(GameState::Play)),
+    )
     .add_systems(Update, apply_drag.run_if(in_state(GameState::Play)))
     .add_systems(
         Update,
@@ -71,15 +99,88 @@ fn main() {
         Update,
         wrap.after(movement).run_if(in_state(GameState::Play)),
     )
-    .add_systems(Update, tick_lifetime.run_if(in_state(GameState::Play)))
-    .add_systems(Update, update_player_ui)
+    .add_systems(
+        Update,
+        (tick_lifetime, (expire_lifetimes, fade_out_lifetimes))
+            .chain()
+            .run_if(in_state(GameState::Play)),
+    )
+    .add_systems(
+        Update,
+        (
+            tick_status_effects.before(apply_damage),
+            tint_status_effects,
+        )
+            .run_if(in_state(GameState::Play)),
+    )
+    .add_systems(Update, (update_player_ui, update_lives_ui, update_boss_bar))
+    .add_systems(Update, respawn_player.run_if(in_state(GameState::Play)))
+    .add_systems(
+        Update,
+        tick_invulnerability.run_if(in_state(GameState::Play)),
+    )
+    .add_systems(
+        Update,
+        (
+            regenerate_shields,
+            update_shield_bubbles.after(apply_damage),
+        )
+            .run_if(in_state(GameState::Play)),
+    )
     .add_systems(
         Update,
         (fade_tutorials, animate).run_if(in_state(GameState::Play)),
     )
     .add_systems(
         Update,
-        (cull_bullets, break_asteroids, hurt_player).after(check_collisions),
+        (cull_bullets, knock_back_asteroids, apply_spin, apply_damage).after(check_collisions),
+    )
+    .add_systems(
+        Update,
+        (play_hit_sounds, knock_back_player).after(apply_damage),
+    )
+    .add_systems(
+        Update,
+        (
+            kill_damaged,
+            (
+                break_asteroids,
+                explode_on_death,
+                drop_loot,
+                play_death_sounds,
+                award_score,
+                lose_life,
+                break_weak_points,
+            ),
+            award_extra_lives,
+            despawn_dead,
+        )
+            .chain()
+            .after(apply_damage)
+            .after(cull_bullets)
+            .after(trigger_mines)
+            .after(tick_detonations)
+            .after(collect_pickups),
+    )
+    .add_systems(
+        Update,
+        (
+            detonate_explosions
+                .after(expire_lifetimes)
+                .before(check_collisions),
+            // Each way of dying goes in turn so anything already Dead is skipped
+            tick_detonations.after(trigger_mines),
+        )
+            .run_if(in_state(GameState::Play)),
+    )
+    .add_systems(
+        Update,
+        (
+            collect_pickups,
+            die_on_contact.after(cull_bullets),
+            trigger_mines.after(die_on_contact),
+        )
+            .after(check_collisions),
     )
     .add_systems(Update, bevy::window::close_on_esc)
     .add_systems(Update, toggle_pause.run_if(input_just_pressed(KeyCode::KeyP)))
@@ -87,7 +188,18 @@ fn main() {
     .insert_resource(UiAnimationTimer(Timer::from_seconds(
         0.5,
         TimerMode::Repeating,
-    )));
+    )))
+    .init_resource::<FactionRelations>()
+    .init_resource::<Halos>()
+    .insert_resource(Run {
+        seed,
+        ..Default::default()
+    })
+    .init_resource::<Waves>()
+    .insert_resource(AsteroidSettings {
+        collide: true,
+        impact_damage: 0.05,
+    });
 
     if devcade {
         println!("Void Break: Detected DEVCADE_PATH, Devcade specifics enabled");
@@ -108,8 +220,22 @@ fn toggle_pause(mut next_state: ResMut<NextState<GameState>>, state: Res<State<G
     }
 }
 
+/// What systems usually need to spawn things with sprites, meshes and sounds
+#[derive(SystemParam)]
+struct Spawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    assets: Res<'w, AssetServer>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    materials: ResMut<'w, Assets<ColorMaterial>>,
+}
+
 /// Spawn the core components needed for basic game function: Background, Player, and Camera
-fn spawn_core(mut commands: Commands, assets: Res<AssetServer>) {
+fn spawn_core(
+    mut commands: Commands,
+    assets: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
     commands.spawn((
         // Background
         SpriteBundle {
@@ -231,31 +357,98 @@ fn spawn_core(mut commands: Commands, assets: Res<AssetServer>) {
                 },
             ));
         });
-    commands.spawn(PlayerBundle {
-        sprite_bundle: SpriteBundle {
-            // TODO: Might want to set sprite size
-            texture: assets.load("basic_player_100.png"),
-            ..Default::default()
-        },
-        player: Player,
-        velocity: Velocity::default(),
-        drag: Drag {
-            translational: 1.5,
-            rotational: 2.0,
-        },
-        wrap: Wrappable,
-        health: Health {
-            health: 100.0,
-            max: 100.0,
-        },
-        affiliation: Affiliation::Friendly,
-        collision: CollisionConfig {
-            radius: 65.0,
-            ..Default::default()
-        },
-        damage: Damage::Basic(50.0),
-        knockback: Knockback(10.0),
-    });
+    spawn_player(
+        &mut commands,
+        &assets,
+        &mut meshes,
+        &mut materials,
+        Vec3::ZERO,
+    );
+}
+
+/// Spawn the player ship and its shield bubble
+fn spawn_player(
+    commands: &mut Commands,
+    assets: &AssetServer,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    position: Vec3,
+) -> Entity {
+    commands
+        .spawn(PlayerBundle {
+            sprite_bundle: SpriteBundle {
+                // TODO: Might want to set sprite size
+                texture: assets.load("basic_player_100.png"),
+                transform: Transform::from_translation(position),
+                ..Default::default()
+            },
+            player: Player,
+            velocity: Velocity::default(),
+            drag: Drag {
+                translational: 1.5,
+                rotational: 2.0,
+            },
+            wrap: Wrappable,
+            health: Health {
+                health: 100.0,
+                max: 100.0,
+            },
+            affiliation: Affiliation::Friendly,
+            collision: CollisionConfig {
+                radius: 65.0,
+                layers: CollisionLayers {
+                    membership: CollisionLayers::SHIP,
+                    filter: CollisionLayers::ALL,
+                },
+                ..Default::default()
+            },
+            damage: Damage::Kinetic(50.0),
+            knockback: Knockback(10.0),
+            mass: Mass(10.0),
+            status_effects: StatusEffects::default(),
+            shield: Shield {
+                current: 50.0,
+                capacity: 50.0,
+                regen_delay: Timer::from_seconds(3.0, TimerMode::Once),
+                regen_rate: 10.0,
+            },
+            invulnerable_on_hit: InvulnerableOnHit(1.5),
+            weapon: Weapon::default(),
+            weapon_rack: WeaponRack {
+                weapons: vec![
+                    Weapon::default(),
+                    Weapon::spread(),
+                    Weapon::laser(),
+                    Weapon::missiles(),
+                    Weapon::mines(),
+                    Weapon::charge_cannon(),
+                ],
+                current: 0,
+            },
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                ShieldBubble {
+                    flash: Timer::from_seconds(0.3, TimerMode::Once),
+                },
+                MaterialMesh2dBundle {
+                    mesh: Mesh2dHandle(meshes.add(Circle::new(75.0))),
+                    material: materials.add(Color::rgba(0.4, 0.7, 1.0, 0.0)),
+                    transform: Transform::from_xyz(0.0, 0.0, 1.0),
+                    ..Default::default()
+                },
+            ));
+            parent.spawn((
+                ChargeGlow,
+                MaterialMesh2dBundle {
+                    mesh: Mesh2dHandle(meshes.add(Circle::new(20.0))),
+                    material: materials.add(Color::rgba(0.6, 1.0, 1.0, 0.0)),
+                    transform: Transform::from_xyz(0.0, 55.0, 2.0).with_scale(Vec3::ZERO),
+                    ..Default::default()
+                },
+            ));
+        })
+        .id()
 }
 
 // Not really needed for anything yet but might be useful later
@@ -276,6 +469,12 @@ struct PlayerBundle {
     collision: CollisionConfig,
     damage: Damage,
     knockback: Knockback,
+    mass: Mass,
+    status_effects: StatusEffects,
+    shield: Shield,
+    invulnerable_on_hit: InvulnerableOnHit,
+    weapon: Weapon,
+    weapon_rack: WeaponRack,
 }
 
 #[derive(Component)]
@@ -284,16 +483,27 @@ struct Player;
 /// Core controls for the player
 // Todo: Make it all delta time based
 fn player_controller(
-    mut query: Query<(&mut Velocity, &Transform), With<Player>>,
+    mut query: Query<
+        (
+            &mut Velocity,
+            &Transform,
+            Option<&StatusEffects>,
+            Option<&mut Weapon>,
+        ),
+        With<Player>,
+    >,
     keyboard: Res<ButtonInput<KeyCode>>,
     time: Res<Time>,
-    mut commands: Commands,
-    assets: Res<AssetServer>,
     devcade_controls: devcaders::DevcadeControls,
     devcade: Option<Res<Devcade>>,
 ) {
-    // If there are ever more than one player, something has gone very wrong
-    let (mut player_velocity, player_transform) = query.single_mut();
+    // If there are ever more than one player, something has gone very wrong.
+    // None just means they're dead and waiting to respawn
+    let Ok((mut player_velocity, player_transform, status_effects, weapon)) =
+        query.get_single_mut()
+    else {
+        return;
+    };
     let forward = player_transform.local_y();
 
     // Once a more configurable input system is set up,
@@ -322,11 +532,21 @@ fn player_controller(
             || devcade_controls.pressed(devcaders::Player::P1, devcaders::Button::StickRight)
             || devcade_controls.pressed(devcaders::Player::P2, devcaders::Button::StickRight);
     }
-    let mut shoot_control = keyboard.just_pressed(KeyCode::Space);
+    let mut shoot_control = keyboard.pressed(KeyCode::Space);
     if devcade.is_some() {
         shoot_control = shoot_control
-            || devcade_controls.just_pressed(devcaders::Player::P1, devcaders::Button::A1)
-            || devcade_controls.just_pressed(devcaders::Player::P2, devcaders::Button::A1);
+            || devcade_controls.pressed(devcaders::Player::P1, devcaders::Button::A1)
+            || devcade_controls.pressed(devcaders::Player::P2, devcaders::Button::A1);
+    }
+    // Stun and EMP are checked by fire_weapons
+    if let Some(mut weapon) = weapon {
+        weapon.trigger = shoot_control;
+    }
+
+    if let Some(status_effects) = status_effects {
+        if status_effects.has(StatusEffect::Stun) {
+            return;
+        }
     }
 
     if forward_control {
@@ -343,619 +563,4645 @@ fn player_controller(
     if right_control {
         player_velocity.rotation_speed -= 2.0 * PI * time.delta_seconds();
     }
+}
 
-    if shoot_control {
-        commands.spawn((
-            ProjectileBundle {
-                affiliation: Affiliation::Friendly,
-                collision: CollisionConfig {
-                    radius: 13.0,
-                    collision_resolution: CollisionResolutionStrat::Prevent,
-                },
-                damage: Damage::Basic(5.0),
-                life: Lifetime {
-                    time: Timer::from_seconds(1.5, TimerMode::Once),
-                },
-                marker: Projectile,
-                velocity: Velocity {
-                    translation_speed: player_velocity.translation_speed + forward * 500.0,
-                    rotation_speed: 0.0,
-                },
-                sprite_bundle: SpriteBundle {
-                    transform: player_transform.clone(),
-                    texture: assets.load("basic_bullet_50.png"),
-                    ..Default::default()
-                },
-            },
-            Bullet,
-            Wrappable,
-            Knockback(5.0),
-        ));
-        commands.spawn(AudioBundle {
-            source: assets.load("shoot1.wav"),
-            settings: PlaybackSettings::DESPAWN,
-        });
-    }
+/// A gun any ship can carry. Whatever controls the ship pulls the trigger and [`fire_weapons`] does the rest
+#[derive(Component, Clone)]
+struct Weapon {
+    kind: WeaponKind,
+    /// Shots per second
+    fire_rate: f32,
+    /// Seconds until the next shot is allowed
+    cooldown: f32,
+    projectile_speed: f32,
+    /// Seconds before projectiles expire
+    lifetime: f32,
+    damage: Damage,
+    knockback: f32,
+    /// Total angle in radians that the projectiles of one shot fan out across
+    spread: f32,
+    /// Projectiles per shot
+    count: u32,
+    /// How the projectiles of each shot are laid out
+    pattern: FirePattern,
+    /// Where a spiral or wave pattern is up to
+    pattern_angle: f32,
+    /// Shots still to go in the current burst
+    burst_left: u32,
+    /// Keeps firing while the trigger is held instead of needing a press for every shot
+    automatic: bool,
+    /// Things each projectile passes through before being used up
+    pierce: u32,
+    /// Times each projectile bounces off asteroids
+    ricochet: u32,
+    split: Option<SplitOnHit>,
+    /// Status effects every hit hands out
+    inflicts: Inflicts,
+    heat: Heat,
+    /// Seconds of holding to fully charge. Zero fires normally, anything else only fires on release
+    charge_time: f32,
+    /// How charged up the weapon is from 0 to 1
+    charge: f32,
+    texture: &'static str,
+    /// Multiplied into the projectile sprite so weapons sharing a texture still look different
+    tint: Color,
+    sound: &'static str,
+    /// Playback speed of the sound, lower is deeper
+    pitch: f32,
+    trigger: bool,
+    trigger_was_held: bool,
 }
 
-#[derive(Component, Default)]
-struct Velocity {
-    translation_speed: Vec3,
-    /// Note: Positive is clockwise
-    rotation_speed: f32,
+#[derive(Clone)]
+enum WeaponKind {
+    /// Plain bullets
+    Projectile,
+    /// Instantly hits the first thing along the beam
+    Laser { range: f32 },
+    /// Steers toward the nearest thing it can hurt and blows up
+    Missile {
+        turn_rate: f32,
+        explosion: Explosion,
+    },
+    /// Left where it was fired and blows up once something it can hurt comes close
+    Mine {
+        trigger_radius: f32,
+        explosion: Explosion,
+    },
 }
 
-fn movement(mut query: Query<(&mut Transform, &Velocity)>, time: Res<Time>) {
-    for (mut transform, velocity) in query.iter_mut() {
-        transform.translation += velocity.translation_speed * time.delta_seconds();
-        transform.rotate_local_z(velocity.rotation_speed * time.delta_seconds());
+impl Default for Weapon {
+    fn default() -> Self {
+        Weapon {
+            kind: WeaponKind::Projectile,
+            fire_rate: 5.0,
+            cooldown: 0.0,
+            projectile_speed: 500.0,
+            lifetime: 1.5,
+            damage: Damage::Kinetic(5.0),
+            knockback: 5.0,
+            spread: 0.0,
+            count: 1,
+            pattern: FirePattern::Fan,
+            pattern_angle: 0.0,
+            burst_left: 0,
+            automatic: true,
+            pierce: 0,
+            ricochet: 0,
+            split: None,
+            inflicts: Inflicts::default(),
+            heat: Heat::default(),
+            charge_time: 0.0,
+            charge: 0.0,
+            texture: "basic_bullet_50.png",
+            tint: Color::WHITE,
+            sound: "shoot1.wav",
+            pitch: 1.0,
+            trigger: false,
+            trigger_was_held: false,
+        }
     }
 }
 
-#[derive(Component)]
-/// 0-1 or more due to delta time, more drag slows faster
-struct Drag {
-    translational: f32,
-    rotational: f32,
+/// Data describing how projectiles come out of a [`Weapon`]
+#[derive(Clone, Copy, Debug)]
+enum FirePattern {
+    /// `count` projectiles fanned evenly across `spread`, straight ahead when there's one
+    Fan,
+    /// Each pull of the trigger fires the fan `shots` times, `interval` seconds apart
+    Burst { shots: u32, interval: f32 },
+    /// `count` projectiles evenly around a full circle
+    Ring,
+    /// A ring that turns by `step` radians every shot
+    Spiral { step: f32 },
+    /// A fan that sweeps `amplitude` radians side to side, moving `step` radians along the wave each shot
+    Wave { amplitude: f32, step: f32 },
 }
 
-fn apply_drag(mut query: Query<(&mut Velocity, &Drag)>, time: Res<Time>) {
-    for (mut velocity, drag) in query.iter_mut() {
-        // I think this is the right way to delta time here... could be wrong
-        velocity.translation_speed *= 1.0 - drag.translational * time.delta_seconds();
-        velocity.rotation_speed *= 1.0 - drag.rotational * time.delta_seconds();
-
-        // TODO: zero it past a threshold. or maybe not...
+impl Weapon {
+    /// Lets cooldown and heat wear off, whether it's mounted or not
+    fn cool_down(&mut self, seconds: f32) {
+        self.cooldown = (self.cooldown - seconds).max(0.0);
+        let heat = &mut self.heat;
+        heat.current = (heat.current - heat.cooling * seconds).max(0.0);
+        heat.overheated = (heat.overheated - seconds).max(0.0);
     }
-}
 
-#[derive(Component)]
-struct MainCamera;
+    /// Angles off the nose for each projectile of the next shot, advancing spirals and waves
+    fn volley_angles(&mut self) -> Vec<f32> {
+        let fan = |count: u32, spread: f32| -> Vec<f32> {
+            (0..count)
+                .map(|i| {
+                    if count > 1 {
+                        spread * (i as f32 / (count - 1) as f32 - 0.5)
+                    } else {
+                        0.0
+                    }
+                })
+                .collect()
+        };
+        let ring = |count: u32, offset: f32| -> Vec<f32> {
+            (0..count)
+                .map(|i| offset + i as f32 * PI * 2.0 / count.max(1) as f32)
+                .collect()
+        };
+        match self.pattern {
+            FirePattern::Fan | FirePattern::Burst { .. } => fan(self.count, self.spread),
+            FirePattern::Ring => ring(self.count, 0.0),
+            FirePattern::Spiral { step } => {
+                self.pattern_angle = (self.pattern_angle + step) % (PI * 2.0);
+                ring(self.count, self.pattern_angle)
+            }
+            FirePattern::Wave { amplitude, step } => {
+                self.pattern_angle = (self.pattern_angle + step) % (PI * 2.0);
+                let sweep = amplitude * self.pattern_angle.sin();
+                fan(self.count, self.spread)
+                    .into_iter()
+                    .map(|angle| angle + sweep)
+                    .collect()
+            }
+        }
+    }
 
-fn camera_controller(
-    player_transform: Query<&Transform, With<Player>>,
-    // This one must explicitly exclude player or Bevy will scream even though no MainCamera has a Player.
-    mut camera_transform: Query<&mut Transform, (With<MainCamera>, Without<Player>)>,
-) {
-    // Again, if theres more than one player, we have a big issue. TODO: If multiplayer later this will be an issue...
-    let player_transform = player_transform.single();
-    let mut camera_transform = camera_transform.single_mut();
+    fn spread() -> Self {
+        Weapon {
+            fire_rate: 2.0,
+            lifetime: 0.8,
+            damage: Damage::Kinetic(4.0),
+            spread: 0.8,
+            count: 5,
+            heat: Heat {
+                per_shot: 20.0,
+                ..Default::default()
+            },
+            texture: "pellet_30.png",
+            tint: Color::rgb(1.0, 0.8, 0.3),
+            sound: "shoot_spread.wav",
+            ..Default::default()
+        }
+    }
 
-    camera_transform.translation = player_transform.translation;
-}
+    fn laser() -> Self {
+        Weapon {
+            kind: WeaponKind::Laser { range: 800.0 },
+            fire_rate: 4.0,
+            heat: Heat {
+                per_shot: 12.0,
+                ..Default::default()
+            },
+            damage: Damage::Energy(6.0),
+            knockback: 2.0,
+            inflicts: Inflicts(vec![(StatusEffect::Burn(3.0), 2.0)]),
+            texture: "laser_beam_64.png",
+            tint: Color::rgb(1.0, 0.2, 0.3),
+            sound: "laser.wav",
+            ..Default::default()
+        }
+    }
 
-#[derive(Component, Default)]
-struct Wrappable;
+    fn missiles() -> Self {
+        Weapon {
+            kind: WeaponKind::Missile {
+                turn_rate: 4.0,
+                explosion: Explosion {
+                    radius: 80.0,
+                    damage: Damage::Explosive(10.0),
+                    knockback: 20.0,
+                    ..Default::default()
+                },
+            },
+            fire_rate: 1.5,
+            projectile_speed: 350.0,
+            heat: Heat {
+                per_shot: 20.0,
+                ..Default::default()
+            },
+            lifetime: 3.0,
+            damage: Damage::Explosive(10.0),
+            texture: "missile_50.png",
+            tint: Color::ORANGE,
+            sound: "missile_launch.wav",
+            ..Default::default()
+        }
+    }
 
-fn wrap(
-    background: Query<&Background>,
-    mut query: Query<(&mut Transform, &Velocity), With<Wrappable>>,
-) {
-    let background = background.single();
-    for (mut transform, velocity) in query.iter_mut() {
-        if transform.translation.x.abs() > background.size.x / 2.0
-            && transform.translation.x.is_sign_negative()
-                == velocity.translation_speed.x.is_sign_negative()
-        {
-            transform.translation.x *= -1.0;
+    fn charge_cannon() -> Self {
+        Weapon {
+            fire_rate: 2.0,
+            projectile_speed: 600.0,
+            damage: Damage::Kinetic(10.0),
+            knockback: 10.0,
+            charge_time: 1.5,
+            heat: Heat {
+                per_shot: 25.0,
+                ..Default::default()
+            },
+            tint: Color::CYAN,
+            pitch: 0.7,
+            ..Default::default()
         }
-        if transform.translation.y.abs() > background.size.y / 2.0
-            && transform.translation.y.is_sign_negative()
-                == velocity.translation_speed.y.is_sign_negative()
-        {
-            transform.translation.y *= -1.0;
+    }
+
+    fn mines() -> Self {
+        Weapon {
+            kind: WeaponKind::Mine {
+                trigger_radius: 120.0,
+                // Whatever survives the blast can't shoot back for a bit
+                explosion: Explosion {
+                    radius: 150.0,
+                    damage: Damage::Explosive(30.0),
+                    knockback: 40.0,
+                    inflicts: Inflicts(vec![(StatusEffect::Emp, 2.0)]),
+                },
+            },
+            fire_rate: 1.0,
+            lifetime: 20.0,
+            automatic: false,
+            // Mines are limited by their fire rate instead
+            heat: Heat {
+                per_shot: 0.0,
+                ..Default::default()
+            },
+            texture: "mine_50.png",
+            tint: Color::RED,
+            sound: "mine_drop.wav",
+            ..Default::default()
         }
     }
 }
 
-#[derive(Component, Default)]
-struct Asteroid;
+/// Builds up with every shot and locks the weapon out for a while if it reaches capacity
+#[derive(Clone)]
+struct Heat {
+    current: f32,
+    capacity: f32,
+    /// Added by every shot
+    per_shot: f32,
+    /// Lost every second
+    cooling: f32,
+    /// Seconds the weapon can't fire after overheating
+    lockout: f32,
+    /// Seconds left until an overheated weapon can fire again
+    overheated: f32,
+}
 
-#[derive(Component, Default)]
-struct Health {
-    // Maybe make enum with hits and hp variants
-    health: f32,
-    max: f32,
+impl Default for Heat {
+    fn default() -> Self {
+        Heat {
+            current: 0.0,
+            capacity: 100.0,
+            per_shot: 8.0,
+            cooling: 30.0,
+            lockout: 2.0,
+            overheated: 0.0,
+        }
+    }
 }
 
-#[derive(Component, Default, Debug)]
-struct CollisionConfig {
-    // This could eb an enum maybe for different types of collision boxes maybe. or contain one along with other info
-    radius: f32,
-    collision_resolution: CollisionResolutionStrat,
+/// Damage, size and knockback of a fully charged shot compared to an uncharged one
+const CHARGE_MAX_SCALE: f32 = 3.0;
+/// Extra things a fully charged shot passes through
+const CHARGE_MAX_PIERCE: u32 = 3;
+
+/// The glow at the nose of a ship that grows as its [`Weapon`] charges
+#[derive(Component)]
+struct ChargeGlow;
+
+fn update_charge_glows(
+    mut glows: Query<(&Parent, &mut Transform, &Handle<ColorMaterial>), With<ChargeGlow>>,
+    weapons: Query<&Weapon>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (parent, mut transform, material) in glows.iter_mut() {
+        let charge = weapons
+            .get(parent.get())
+            .map_or(0.0, |weapon| weapon.charge);
+        transform.scale = Vec3::splat(charge);
+        if let Some(material) = materials.get_mut(material) {
+            material.color.set_a(0.3 + 0.6 * charge);
+        }
+    }
 }
 
-#[derive(Bundle, Default)]
-struct AsteroidBundle {
-    collision: CollisionConfig,
-    health: Health,
-    sprite_bundle: SpriteBundle,
-    velocity: Velocity,
-    wrap: Wrappable,
-    asteroid: Asteroid,
-    affiliation: Affiliation,
-    damage: Damage,
-    knockback: Knockback,
+/// Every weapon a ship has, only the one at `current` is mounted as its [`Weapon`]
+#[derive(Component)]
+struct WeaponRack {
+    weapons: Vec<Weapon>,
+    current: usize,
 }
 
-fn spawn_asteroids(
-    mut commands: Commands,
-    assets: Res<AssetServer>,
-    mut rng: ResMut<GlobalEntropy<WyRand>>,
+fn switch_weapons(
+    mut query: Query<(&mut Weapon, &mut WeaponRack), With<Player>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    devcade_controls: devcaders::DevcadeControls,
+    devcade: Option<Res<Devcade>>,
 ) {
-    for _ in 0..4 {
-        let size: f32 = rng.gen_range(10.0..100.0);
-        let direction = rng.gen_range(0.0..PI * 2.0);
-        let speed = rng.gen_range(0.0..3000.0 / size);
-        commands.spawn(AsteroidBundle {
-            collision: CollisionConfig {
-                radius: size / 2.0,
-                ..Default::default()
-            },
-            health: Health {
-                health: size / 2.0,
-                max: size / 2.0,
-            },
-            sprite_bundle: SpriteBundle {
-                texture: assets.load("basic_asteroid_100.png"),
-                transform: Transform::from_xyz(
-                    rng.gen_range(-500.0..500.0), // TODO: set this from background
-                    rng.gen_range(-500.0..500.0),
-                    0.0,
-                ),
-                sprite: Sprite {
-                    custom_size: Some(Vec2 { x: size, y: size }),
-                    ..Default::default()
-                },
-                ..Default::default()
-            },
-            velocity: Velocity {
-                translation_speed: Vec3 {
-                    x: direction.cos(),
-                    y: direction.sin(),
-                    z: 0.0,
-                } * speed,
-                rotation_speed: rng.gen_range(-100.0 / size..100.0 / size),
-            },
-            damage: Damage::Basic(size / 3.0),
-            knockback: Knockback(size * 2.0),
-            ..Default::default()
-        });
+    let mut switch_control = keyboard.just_pressed(KeyCode::KeyQ);
+    if devcade.is_some() {
+        switch_control = switch_control
+            || devcade_controls.just_pressed(devcaders::Player::P1, devcaders::Button::A2)
+            || devcade_controls.just_pressed(devcaders::Player::P2, devcaders::Button::A2);
+    }
+    if !switch_control {
+        return;
+    }
+    for (mut weapon, mut rack) in query.iter_mut() {
+        if rack.weapons.is_empty() {
+            continue;
+        }
+        // Put the mounted one back first so it keeps its cooldown
+        let current = rack.current;
+        rack.weapons[current] = weapon.clone();
+        rack.current = (current + 1) % rack.weapons.len();
+        *weapon = rack.weapons[rack.current].clone();
     }
 }
 
-// ============================================================= Ripped from book fps counter
+/// Weapons in the rack keep cooling off while they aren't mounted
+fn cool_racked_weapons(mut racks: Query<&mut WeaponRack>, time: Res<Time>) {
+    for mut rack in racks.iter_mut() {
+        let current = rack.current;
+        for (i, weapon) in rack.weapons.iter_mut().enumerate() {
+            if i != current {
+                weapon.cool_down(time.delta_seconds());
+            }
+        }
+    }
+}
 
-/// Marker to find the container entity so we can show/hide the FPS counter
-#[derive(Component)]
-struct FpsRoot;
+/// Everything a laser needs to find what it hits
+#[derive(SystemParam)]
+struct LaserTargets<'w, 's> {
+    targets: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static CollisionConfig,
+            Option<&'static Affiliation>,
+        ),
+        With<Health>,
+    >,
+    placements: Placements<'w, 's>,
+    relations: Res<'w, FactionRelations>,
+}
 
-/// Marker to find the text entity so we can update it
-#[derive(Component)]
-struct FpsText;
+/// Halo around hostile shots so they stand out from the player's.
+/// Made once and shared so firing doesn't keep adding assets
+#[derive(Resource)]
+struct Halos {
+    mesh: Mesh2dHandle,
+    /// One material for each weapon tint, made the first time it's needed
+    materials: HashMap<[u8; 4], Handle<ColorMaterial>>,
+}
+impl FromWorld for Halos {
+    fn from_world(world: &mut World) -> Self {
+        Halos {
+            mesh: Mesh2dHandle(world.resource_mut::<Assets<Mesh>>().add(Circle::new(20.0))),
+            materials: HashMap::new(),
+        }
+    }
+}
 
-fn setup_fps_counter(mut commands: Commands, main_camera: Query<Entity, With<MainCamera>>) {
-    // create our UI root node
-    // this is the wrapper/container for the text
-    // TODO: single can panic
-    let root = commands
-        .spawn((
-            TargetCamera(main_camera.single()),
-            FpsRoot,
-            NodeBundle {
-                // give it a dark background for readability
-                background_color: BackgroundColor(Color::BLACK.with_a(0.5)),
-                // make it "always on top" by setting the Z index to maximum
-                // we want it to be displayed over all other UI
-                z_index: ZIndex::Global(i32::MAX),
-                style: Style {
-                    position_type: PositionType::Absolute,
-                    // position it at the top-right corner
-                    // 1% away from the top window edge
-                    right: Val::Percent(1.),
-                    top: Val::Percent(1.),
-                    // set bottom/left to Auto, so it can be
-                    // automatically sized depending on the text
-                    bottom: Val::Auto,
-                    left: Val::Auto,
-                    // give it some padding for readability
-                    padding: UiRect::all(Val::Px(4.0)),
-                    ..Default::default()
-                },
-                ..Default::default()
-            },
-        ))
-        .id();
-    // create our text
-    let text_fps = commands
-        .spawn((
-            FpsText,
-            TextBundle {
-                // use two sections, so it is easy to update just the number
-                text: Text::from_sections([
-                    TextSection {
-                        value: "FPS: ".into(),
-                        style: TextStyle {
-                            font_size: 16.0,
-                            color: Color::WHITE,
-                            // if you want to use your game's font asset,
-                            // uncomment this and provide the handle:
-                            // font: my_font_handle
-                            ..default()
+fn fire_weapons(
+    mut weapons: Query<(
+        Entity,
+        &mut Weapon,
+        &Transform,
+        Option<&Velocity>,
+        Option<&Affiliation>,
+        Option<&StatusEffects>,
+    )>,
+    laser_targets: LaserTargets,
+    mut halos: ResMut<Halos>,
+    mut collisions: EventWriter<CollisionEvent>,
+    time: Res<Time>,
+    mut spawner: Spawner,
+) {
+    let LaserTargets {
+        targets,
+        placements,
+        relations,
+    } = laser_targets;
+    for (entity, mut weapon, transform, velocity, affiliation, status_effects) in weapons.iter_mut()
+    {
+        weapon.cool_down(time.delta_seconds());
+        let pulled = weapon.trigger && (weapon.automatic || !weapon.trigger_was_held);
+        let released = !weapon.trigger && weapon.trigger_was_held;
+        weapon.trigger_was_held = weapon.trigger;
+
+        let disabled = weapon.heat.overheated > 0.0
+            || status_effects.map_or(false, |status_effects| {
+                status_effects.has(StatusEffect::Stun) || status_effects.has(StatusEffect::Emp)
+            });
+        if weapon.charge_time > 0.0 {
+            // Charge builds while held and the shot only goes off on release
+            if disabled {
+                weapon.charge = 0.0;
+                continue;
+            }
+            if weapon.trigger && weapon.cooldown <= 0.0 {
+                weapon.charge =
+                    (weapon.charge + time.delta_seconds() / weapon.charge_time).min(1.0);
+                continue;
+            }
+            if !released || weapon.charge <= 0.0 {
+                continue;
+            }
+        } else if weapon.burst_left > 0 {
+            // The rest of a burst goes off on its own once started
+            if disabled || weapon.cooldown > 0.0 {
+                continue;
+            }
+        } else if !pulled || disabled || weapon.cooldown > 0.0 {
+            continue;
+        }
+        let charge = weapon.charge;
+        weapon.charge = 0.0;
+        weapon.cooldown = 1.0 / weapon.fire_rate;
+        if let FirePattern::Burst { shots, interval } = weapon.pattern {
+            weapon.burst_left = match weapon.burst_left {
+                0 => shots.saturating_sub(1),
+                left => left - 1,
+            };
+            if weapon.burst_left > 0 {
+                weapon.cooldown = interval;
+            }
+        }
+        let angles = weapon.volley_angles();
+        let heat = &mut weapon.heat;
+        heat.current += heat.per_shot;
+        if heat.current >= heat.capacity {
+            heat.current = heat.capacity;
+            heat.overheated = heat.lockout;
+        }
+        let weapon: &Weapon = &weapon;
+        let affiliation = affiliation.copied().unwrap_or_default();
+        let scale = 1.0 + charge * (CHARGE_MAX_SCALE - 1.0);
+        let damage = weapon.damage.scaled(scale);
+        let pierce = weapon.pierce + (charge * CHARGE_MAX_PIERCE as f32) as u32;
+
+        spawner.commands.spawn(AudioBundle {
+            source: spawner.assets.load(weapon.sound),
+            settings: PlaybackSettings::DESPAWN.with_speed(weapon.pitch),
+        });
+
+        match &weapon.kind {
+            WeaponKind::Laser { range } => {
+                let origin = transform.translation.truncate();
+                let direction = transform.local_y().truncate();
+                let beam_layers = CollisionLayers {
+                    membership: CollisionLayers::PROJECTILE,
+                    filter: CollisionLayers::ALL,
+                };
+                // Ray against every circle it's allowed to hurt, keeping the closest.
+                // The beam takes the same side its projectiles would
+                let hit = targets
+                    .iter()
+                    .filter_map(|(part, config, part_affiliation)| {
+                        let (body, placed) = placements.locate(part)?;
+                        // Parts take their body's side unless they have their own
+                        let target_affiliation =
+                            part_affiliation.or_else(|| targets.get(body).ok()?.2);
+                        if body == entity
+                            || config.collision_resolution == CollisionResolutionStrat::Sensor
+                            || !beam_layers.interacts_with(&config.layers)
+                            || !relations
+                                .between(Some(affiliation), target_affiliation.copied())
+                                .damages()
+                        {
+                            return None;
+                        }
+                        let to_center = placed.translation.truncate() - origin;
+                        let along = to_center.dot(direction);
+                        let miss_squared = to_center.length_squared() - along.powi(2);
+                        let half_chord_squared = config.radius.powi(2) - miss_squared;
+                        if half_chord_squared < 0.0 || along + half_chord_squared.sqrt() < 0.0 {
+                            return None;
+                        }
+                        let distance = (along - half_chord_squared.sqrt()).max(0.0);
+                        (distance <= *range).then_some((body, part, distance))
+                    })
+                    .min_by(|a, b| a.2.total_cmp(&b.2));
+
+                let length = hit.map_or(*range, |(_, _, distance)| distance);
+                spawner.commands.spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: weapon.tint,
+                            custom_size: Some(Vec2::new(10.0, length)),
+                            ..Default::default()
+                        },
+                        texture: spawner.assets.load(weapon.texture),
+                        transform: Transform {
+                            translation: (origin + direction * length / 2.0).extend(5.0),
+                            rotation: transform.rotation,
+                            ..Default::default()
                         },
+                        ..Default::default()
                     },
-                    TextSection {
-                        value: " N/A".into(),
-                        style: TextStyle {
-                            font_size: 16.0,
-                            color: Color::WHITE,
-                            // if you want to use your game's font asset,
-                            // uncomment this and provide the handle:
-                            // font: my_font_handle
-                            ..default()
+                    Lifetime {
+                        time: Timer::from_seconds(0.15, TimerMode::Once),
+                        on_expire: Expire::FadeOut(0.15),
+                    },
+                ));
+                if let Some((body, part, _)) = hit {
+                    collisions.send(CollisionEvent {
+                        entities: [entity, body],
+                        parts: [entity, part],
+                        damage: [Some(damage.clone()), None],
+                        inflicts: [Some(weapon.inflicts.clone()), None],
+                        direction,
+                        knockback: [weapon.knockback, 0.0],
+                        spin: [0.0; 2],
+                    });
+                }
+            }
+            WeaponKind::Mine {
+                trigger_radius,
+                explosion,
+            } => {
+                spawner.commands.spawn((
+                    Mine,
+                    affiliation,
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: weapon.tint,
+                            ..Default::default()
                         },
+                        transform: Transform::from_translation(transform.translation),
+                        texture: spawner.assets.load(weapon.texture),
+                        ..Default::default()
                     },
-                ]),
-                ..Default::default()
-            },
-        ))
-        .id();
-    commands.entity(root).push_children(&[text_fps]);
+                    CollisionConfig {
+                        radius: *trigger_radius,
+                        collision_resolution: CollisionResolutionStrat::Sensor,
+                        layers: CollisionLayers {
+                            membership: CollisionLayers::PROJECTILE,
+                            filter: CollisionLayers::SHIP | CollisionLayers::ASTEROID,
+                        },
+                    },
+                    ExplodesOnDeath(explosion.clone()),
+                    // Fizzles out without exploding if nothing sets it off
+                    Lifetime {
+                        time: Timer::from_seconds(weapon.lifetime, TimerMode::Once),
+                        on_expire: Expire::FadeOut(2.0),
+                    },
+                ));
+            }
+            WeaponKind::Projectile | WeaponKind::Missile { .. } => {
+                let inherited = velocity.map_or(Vec3::ZERO, |velocity| velocity.translation_speed);
+                // Hostile shots get a halo so they stand out from the player's
+                let halo = (affiliation != Affiliation::Friendly).then(|| {
+                    let material = halos
+                        .materials
+                        .entry(weapon.tint.as_rgba_u8())
+                        .or_insert_with(|| spawner.materials.add(weapon.tint.with_a(0.35)))
+                        .clone();
+                    (halos.mesh.clone(), material)
+                });
+                for angle in angles {
+                    let mut transform = *transform;
+                    transform.rotate_local_z(angle);
+                    transform.scale *= scale;
+                    let mut projectile = spawner.commands.spawn((
+                        ProjectileBundle {
+                            affiliation,
+                            collision: CollisionConfig {
+                                radius: 13.0 * scale,
+                                collision_resolution: CollisionResolutionStrat::Prevent,
+                                layers: CollisionLayers {
+                                    membership: CollisionLayers::PROJECTILE,
+                                    filter: CollisionLayers::ALL,
+                                },
+                            },
+                            damage: damage.clone(),
+                            life: Lifetime {
+                                time: Timer::from_seconds(weapon.lifetime, TimerMode::Once),
+                                // Missiles still blow up when they run out, plain shots just vanish
+                                on_expire: match (&weapon.kind, &halo) {
+                                    (WeaponKind::Missile { .. }, _) => Expire::Die,
+                                    (_, Some(_)) => Expire::DespawnRecursive,
+                                    (_, None) => Expire::Despawn,
+                                },
+                            },
+                            marker: Projectile,
+                            mass: Mass(1.0),
+                            already_hit: AlreadyHit::default(),
+                            velocity: Velocity {
+                                translation_speed: inherited
+                                    + transform.local_y() * weapon.projectile_speed,
+                                rotation_speed: 0.0,
+                            },
+                            sprite_bundle: SpriteBundle {
+                                sprite: Sprite {
+                                    color: weapon.tint,
+                                    ..Default::default()
+                                },
+                                transform,
+                                texture: spawner.assets.load(weapon.texture),
+                                ..Default::default()
+                            },
+                        },
+                        Bullet,
+                        Wrappable,
+                        Knockback(weapon.knockback * scale),
+                    ));
+                    if let WeaponKind::Missile {
+                        turn_rate,
+                        explosion,
+                    } = &weapon.kind
+                    {
+                        projectile.insert((Homing(*turn_rate), ExplodesOnDeath(explosion.clone())));
+                    }
+                    if let Some((mesh, material)) = &halo {
+                        projectile.with_children(|parent| {
+                            parent.spawn(MaterialMesh2dBundle {
+                                mesh: mesh.clone(),
+                                material: material.clone(),
+                                transform: Transform::from_xyz(0.0, 0.0, -1.0),
+                                ..Default::default()
+                            });
+                        });
+                    }
+                    if pierce > 0 {
+                        projectile.insert(Pierce(pierce));
+                    }
+                    if weapon.ricochet > 0 {
+                        projectile.insert(Ricochet(weapon.ricochet));
+                    }
+                    if let Some(split) = &weapon.split {
+                        projectile.insert(split.clone());
+                    }
+                    if !weapon.inflicts.0.is_empty() {
+                        projectile.insert(weapon.inflicts.clone());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Turns toward the nearest thing it's allowed to hurt, in radians per second
+#[derive(Component)]
+struct Homing(f32);
+
+fn steer_homing(
+    mut missiles: Query<(&Homing, &mut Velocity, &mut Transform, Option<&Affiliation>)>,
+    targets: Query<
+        (&Transform, Option<&Affiliation>),
+        (With<Health>, Without<Homing>, Without<Parent>),
+    >,
+    relations: Res<FactionRelations>,
+    time: Res<Time>,
+) {
+    for (homing, mut velocity, mut transform, affiliation) in missiles.iter_mut() {
+        let position = transform.translation.truncate();
+        let heading = velocity.translation_speed.truncate();
+        let Some(target) = targets
+            .iter()
+            .filter(|(_, target_affiliation)| {
+                relations
+                    .between(affiliation.copied(), target_affiliation.copied())
+                    .damages()
+            })
+            .map(|(target_transform, _)| target_transform.translation.truncate())
+            .min_by(|a, b| {
+                a.distance_squared(position)
+                    .total_cmp(&b.distance_squared(position))
+            })
+        else {
+            continue;
+        };
+        if heading == Vec2::ZERO {
+            continue;
+        }
+
+        let max_turn = homing.0 * time.delta_seconds();
+        let turn = heading
+            .angle_between(target - position)
+            .clamp(-max_turn, max_turn);
+        let heading = Vec2::from_angle(turn).rotate(heading);
+        velocity.translation_speed = heading.extend(velocity.translation_speed.z);
+        transform.rotation = Quat::from_rotation_arc_2d(Vec2::Y, heading.normalize());
+    }
+}
+
+/// Marks a dropped mine. Its sensor is what notices things coming close
+#[derive(Component)]
+struct Mine;
+
+fn trigger_mines(
+    mines: Query<&Affiliation, (With<Mine>, Without<Dead>)>,
+    affiliations: Query<Option<&Affiliation>, With<Health>>,
+    relations: Res<FactionRelations>,
+    mut sensor_events: EventReader<SensorEvent>,
+    mut died: EventWriter<Died>,
+    mut commands: Commands,
+) {
+    let mut triggered = Vec::new();
+    for sensed in sensor_events.read() {
+        let (Ok(mine), Ok(other)) = (mines.get(sensed.sensor), affiliations.get(sensed.other))
+        else {
+            continue;
+        };
+        if triggered.contains(&sensed.sensor)
+            || !relations.between(Some(*mine), other.copied()).damages()
+        {
+            continue;
+        }
+        triggered.push(sensed.sensor);
+        commands.entity(sensed.sensor).insert(Dead);
+        died.send(Died {
+            entity: sensed.sensor,
+            cause: DeathCause::Spent,
+        });
+    }
+}
+
+#[derive(Component, Default)]
+struct Velocity {
+    translation_speed: Vec3,
+    /// Note: Positive is clockwise
+    rotation_speed: f32,
+}
+
+fn movement(
+    mut query: Query<(&mut Transform, &Velocity, Option<&StatusEffects>)>,
+    time: Res<Time>,
+) {
+    for (mut transform, velocity, status_effects) in query.iter_mut() {
+        let scale = status_effects.map_or(1.0, |status_effects| status_effects.speed_scale());
+        transform.translation += velocity.translation_speed * scale * time.delta_seconds();
+        transform.rotate_local_z(velocity.rotation_speed * time.delta_seconds());
+    }
+}
+
+#[derive(Component)]
+/// 0-1 or more due to delta time, more drag slows faster
+struct Drag {
+    translational: f32,
+    rotational: f32,
+}
+
+fn apply_drag(mut query: Query<(&mut Velocity, &Drag)>, time: Res<Time>) {
+    for (mut velocity, drag) in query.iter_mut() {
+        // I think this is the right way to delta time here... could be wrong
+        velocity.translation_speed *= 1.0 - drag.translational * time.delta_seconds();
+        velocity.rotation_speed *= 1.0 - drag.rotational * time.delta_seconds();
+
+        // TODO: zero it past a threshold. or maybe not...
+    }
+}
+
+#[derive(Component)]
+struct MainCamera;
+
+fn camera_controller(
+    player_transform: Query<&Transform, With<Player>>,
+    // This one must explicitly exclude player or Bevy will scream even though no MainCamera has a Player.
+    mut camera_transform: Query<&mut Transform, (With<MainCamera>, Without<Player>)>,
+) {
+    // Again, if theres more than one player, we have a big issue. TODO: If multiplayer later this will be an issue...
+    // Camera stays put while the player is dead
+    let Ok(player_transform) = player_transform.get_single() else {
+        return;
+    };
+    let mut camera_transform = camera_transform.single_mut();
+
+    camera_transform.translation = player_transform.translation;
+}
+
+#[derive(Component, Default)]
+struct Wrappable;
+
+fn wrap(
+    background: Query<&Background>,
+    mut query: Query<(&mut Transform, &Velocity), With<Wrappable>>,
+) {
+    let background = background.single();
+    for (mut transform, velocity) in query.iter_mut() {
+        if transform.translation.x.abs() > background.size.x / 2.0
+            && transform.translation.x.is_sign_negative()
+                == velocity.translation_speed.x.is_sign_negative()
+        {
+            transform.translation.x *= -1.0;
+        }
+        if transform.translation.y.abs() > background.size.y / 2.0
+            && transform.translation.y.is_sign_negative()
+                == velocity.translation_speed.y.is_sign_negative()
+        {
+            transform.translation.y *= -1.0;
+        }
+    }
+}
+
+/// Shortest offset from one point to another, which might be across the edge of the world
+fn wrapped_offset(from: Vec2, to: Vec2, world_size: Vec2) -> Vec2 {
+    let offset = to - from;
+    offset - world_size * (offset / world_size).round()
+}
+
+#[derive(Component, Default)]
+struct Asteroid;
+
+#[derive(Component, Default)]
+struct Health {
+    // Hit based health is done by adding a HitCounter alongside this
+    health: f32,
+    max: f32,
+}
+
+/// Makes something die after exactly `max` hits no matter how hard each one is.
+/// [`Health`] is kept in step as the fraction of hits left so bars and death checks work as normal
+#[derive(Component, Debug)]
+struct HitCounter {
+    remaining: u32,
+    max: u32,
+}
+
+/// The most health a single hit can take off
+#[derive(Component, Debug)]
+struct DamageCap(f32);
+
+#[derive(Component, Default, Debug)]
+struct CollisionConfig {
+    // This could eb an enum maybe for different types of collision boxes maybe. or contain one along with other info
+    radius: f32,
+    collision_resolution: CollisionResolutionStrat,
+    layers: CollisionLayers,
+}
+
+/// Which kinds of body something is and which kinds it will touch.
+/// Both sides have to accept each other for a collision to happen at all.
+#[derive(Clone, Copy, Debug)]
+struct CollisionLayers {
+    membership: u32,
+    filter: u32,
+}
+impl CollisionLayers {
+    const SHIP: u32 = 1 << 0;
+    const ASTEROID: u32 = 1 << 1;
+    const PROJECTILE: u32 = 1 << 2;
+    const PICKUP: u32 = 1 << 3;
+    const ALL: u32 = u32::MAX;
+
+    fn interacts_with(&self, other: &CollisionLayers) -> bool {
+        self.membership & other.filter != 0 && other.membership & self.filter != 0
+    }
+}
+impl Default for CollisionLayers {
+    fn default() -> Self {
+        CollisionLayers {
+            membership: CollisionLayers::ALL,
+            filter: CollisionLayers::ALL,
+        }
+    }
+}
+
+/// Damage dealt per unit of closing speed when bumping into something that isn't an enemy,
+/// like two asteroids smashing together.
+#[derive(Component, Default, Debug)]
+struct ImpactDamage(f32);
+
+/// Settings for how asteroids treat each other
+#[derive(Resource)]
+struct AsteroidSettings {
+    /// Whether asteroids bounce off each other or pass straight through
+    collide: bool,
+    /// Damage asteroids deal each other per unit of closing speed, 0 for none
+    impact_damage: f32,
+}
+impl AsteroidSettings {
+    fn layers(&self) -> CollisionLayers {
+        CollisionLayers {
+            membership: CollisionLayers::ASTEROID,
+            filter: if self.collide {
+                CollisionLayers::ALL
+            } else {
+                CollisionLayers::ALL & !CollisionLayers::ASTEROID
+            },
+        }
+    }
+}
+
+#[derive(Component, Default, Debug)]
+struct AsteroidShape {
+    /// Around its center, counterclockwise
+    outline: Vec<Vec2>,
+    /// Everything random about its shape and how it breaks comes from this, not the shared rng,
+    /// so shapes depend only on the run seed
+    seed: u64,
+}
+
+/// Corners of a convex outline in local space that [`check_collisions`] uses instead of the circle.
+/// The circle still has to reach every corner since it's used to rule out pairs quickly
+#[derive(Component, Default, Debug)]
+struct ConvexHull(Vec<Vec2>);
+
+/// A jagged, roughly round outline around the origin, counterclockwise
+fn asteroid_outline(rng: &mut impl Rng, radius: f32) -> Vec<Vec2> {
+    let corners = rng.gen_range(9..15);
+    (0..corners)
+        .map(|i| {
+            let angle = (i as f32 + rng.gen_range(-0.3..0.3)) / corners as f32 * PI * 2.0;
+            Vec2::from_angle(angle) * radius * rng.gen_range(0.7..1.0)
+        })
+        .collect()
+}
+
+/// Area and centroid of a simple polygon
+fn polygon_area_centroid(points: &[Vec2]) -> (f32, Vec2) {
+    let mut area = 0.0;
+    let mut centroid = Vec2::ZERO;
+    for (a, b) in points.iter().zip(points.iter().cycle().skip(1)) {
+        let cross = a.perp_dot(*b);
+        area += cross;
+        centroid += (*a + *b) * cross;
+    }
+    area /= 2.0;
+    if area.abs() < f32::EPSILON {
+        return (
+            0.0,
+            points.iter().sum::<Vec2>() / points.len().max(1) as f32,
+        );
+    }
+    (area.abs(), centroid / (6.0 * area))
+}
+
+/// Smallest convex polygon around the points, counterclockwise
+fn convex_hull(points: &[Vec2]) -> Vec<Vec2> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    let mut hull: Vec<Vec2> = Vec::new();
+    // Lower half then upper half, dropping any corner that would turn clockwise
+    for half in [sorted.clone(), sorted.into_iter().rev().collect()] {
+        let start = hull.len();
+        for point in half {
+            while hull.len() >= start + 2
+                && (hull[hull.len() - 1] - hull[hull.len() - 2])
+                    .perp_dot(point - hull[hull.len() - 1])
+                    <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        // The last corner of each half starts the next one
+        hull.pop();
+    }
+    hull
+}
+
+/// Cuts a counterclockwise polygon along the line through `point` across `normal`.
+/// A concave polygon can cross the line more than twice, so there can be more than two pieces
+fn slice_polygon(points: &[Vec2], point: Vec2, normal: Vec2) -> Vec<Vec<Vec2>> {
+    // The outline with the crossings added in, remembering which corners are crossings
+    let mut outline: Vec<(Vec2, bool)> = Vec::new();
+    for (a, b) in points.iter().zip(points.iter().cycle().skip(1)) {
+        let (side_a, side_b) = ((*a - point).dot(normal), (*b - point).dot(normal));
+        outline.push((*a, false));
+        if (side_a < 0.0) != (side_b < 0.0) {
+            outline.push((a.lerp(*b, side_a / (side_a - side_b)), true));
+        }
+    }
+    let mut crossings: Vec<usize> = (0..outline.len()).filter(|i| outline[*i].1).collect();
+    if crossings.len() < 2 {
+        return vec![points.to_vec()];
+    }
+    // Inside the polygon, the line runs between the first and second crossing along it,
+    // the third and fourth, and so on
+    let along = normal.perp();
+    crossings.sort_by(|a, b| {
+        outline[*a]
+            .0
+            .dot(along)
+            .total_cmp(&outline[*b].0.dot(along))
+    });
+    let mut partners = HashMap::new();
+    for pair in crossings.chunks_exact(2) {
+        partners.insert(pair[0], pair[1]);
+        partners.insert(pair[1], pair[0]);
+    }
+
+    // Follow the outline from each corner not yet in a piece, cutting across at every crossing
+    let mut pieces = Vec::new();
+    let mut used = vec![false; outline.len()];
+    for start in 0..outline.len() {
+        if used[start] || outline[start].1 {
+            continue;
+        }
+        let mut piece = Vec::new();
+        let mut current = start;
+        loop {
+            used[current] = true;
+            piece.push(outline[current].0);
+            if let Some(partner) = partners.get(&current) {
+                piece.push(outline[*partner].0);
+                current = *partner;
+            }
+            current = (current + 1) % outline.len();
+            // Crossings are shared between pieces so only corners can close one, and if the
+            // outline isn't simple it might never come back around
+            if current == start || piece.len() > outline.len() {
+                break;
+            }
+        }
+        pieces.push(piece);
+    }
+    pieces
+}
+
+/// Splits a counterclockwise polygon into triangles by clipping off ears
+fn triangulate(points: &[Vec2]) -> Vec<u32> {
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut indices = Vec::new();
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let corners = |i: usize| {
+            [
+                remaining[(i + count - 1) % count],
+                remaining[i],
+                remaining[(i + 1) % count],
+            ]
+        };
+        let ear = (0..count).find(|i| {
+            let [a, b, c] = corners(*i).map(|corner| points[corner]);
+            let inside = |p: Vec2| {
+                (b - a).perp_dot(p - a) >= 0.0
+                    && (c - b).perp_dot(p - b) >= 0.0
+                    && (a - c).perp_dot(p - c) >= 0.0
+            };
+            (b - a).perp_dot(c - b) > 0.0
+                && !remaining
+                    .iter()
+                    .map(|corner| points[*corner])
+                    .any(|p| p != a && p != b && p != c && inside(p))
+        });
+        // Degenerate leftovers just get fanned
+        let Some(ear) = ear else {
+            break;
+        };
+        indices.extend(corners(ear).map(|corner| corner as u32));
+        remaining.remove(ear);
+    }
+    for i in 1..remaining.len().saturating_sub(1) {
+        indices.extend([remaining[0], remaining[i], remaining[i + 1]].map(|corner| corner as u32));
+    }
+    indices
+}
+
+/// A flat mesh filling an outline, with the edges of the texture stretched out to `reach`
+fn polygon_mesh(points: &[Vec2], reach: f32) -> Mesh {
+    let scale = 0.5 / reach.max(f32::EPSILON);
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        points
+            .iter()
+            .map(|point| [point.x, point.y, 0.0])
+            .collect::<Vec<_>>(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; points.len()])
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_UV_0,
+        points
+            .iter()
+            .map(|point| [0.5 + point.x * scale, 0.5 - point.y * scale])
+            .collect::<Vec<_>>(),
+    )
+    .with_inserted_indices(Indices::U32(triangulate(points)))
+}
+
+#[derive(Bundle, Default)]
+struct AsteroidBundle {
+    collision: CollisionConfig,
+    hull: ConvexHull,
+    shape: AsteroidShape,
+    health: Health,
+    mesh_bundle: MaterialMesh2dBundle<ColorMaterial>,
+    velocity: Velocity,
+    wrap: Wrappable,
+    asteroid: Asteroid,
+    affiliation: Affiliation,
+    damage: Damage,
+    knockback: Knockback,
+    mass: Mass,
+    impact_damage: ImpactDamage,
+    status_effects: StatusEffects,
+    fragments: Fragments,
+    death_sound: DeathSound,
+    score: ScoreValue,
+    loot: DropsLoot,
+    material: AsteroidMaterial,
+}
+
+/// What an asteroid is made of, which changes how it looks, how tough it is and what happens when it breaks
+#[derive(Component, Clone, Copy, PartialEq, Default, Debug)]
+enum AsteroidMaterial {
+    #[default]
+    Rock,
+    /// Tough and hits hard
+    Metal,
+    /// Fragile but shatters into lots of little pieces
+    Ice,
+    /// Blows up when broken
+    Explosive,
+    /// Always drops currency
+    Crystal,
+}
+
+const ASTEROID_MATERIALS: [AsteroidMaterial; 5] = [
+    AsteroidMaterial::Rock,
+    AsteroidMaterial::Metal,
+    AsteroidMaterial::Ice,
+    AsteroidMaterial::Explosive,
+    AsteroidMaterial::Crystal,
+];
+
+impl AsteroidMaterial {
+    /// How likely this is to be picked compared to the others
+    fn spawn_weight(&self) -> f32 {
+        match self {
+            AsteroidMaterial::Rock => 10.0,
+            AsteroidMaterial::Metal => 3.0,
+            AsteroidMaterial::Ice => 3.0,
+            AsteroidMaterial::Explosive => 2.0,
+            AsteroidMaterial::Crystal => 1.0,
+        }
+    }
+
+    fn texture(&self) -> &'static str {
+        match self {
+            AsteroidMaterial::Rock => "asteroid_rock_128.png",
+            AsteroidMaterial::Metal => "asteroid_metal_128.png",
+            AsteroidMaterial::Ice => "asteroid_ice_128.png",
+            AsteroidMaterial::Explosive => "asteroid_explosive_128.png",
+            AsteroidMaterial::Crystal => "asteroid_crystal_128.png",
+        }
+    }
+
+    /// Multiplier on health, which is otherwise from size
+    fn toughness(&self) -> f32 {
+        match self {
+            AsteroidMaterial::Metal => 3.0,
+            AsteroidMaterial::Ice => 0.5,
+            AsteroidMaterial::Explosive => 0.75,
+            AsteroidMaterial::Rock | AsteroidMaterial::Crystal => 1.0,
+        }
+    }
+
+    /// Multiplier on mass and knockback
+    fn heft(&self) -> f32 {
+        match self {
+            AsteroidMaterial::Metal => 2.5,
+            AsteroidMaterial::Ice => 0.7,
+            _ => 1.0,
+        }
+    }
+
+    fn fragments(&self) -> Fragments {
+        match self {
+            AsteroidMaterial::Ice => Fragments {
+                max_pieces: 10,
+                min_pieces: 4,
+                piece_size: 5.0,
+            },
+            _ => Fragments::default(),
+        }
+    }
+}
+
+/// Spawns an asteroid drifting and spinning in a random direction
+fn spawn_asteroid(
+    spawner: &mut Spawner,
+    settings: &AsteroidSettings,
+    rng: &mut impl Rng,
+    position: Vec3,
+    size: f32,
+    material: AsteroidMaterial,
+    seed: u64,
+) {
+    let direction = rng.gen_range(0.0..PI * 2.0);
+    let speed = rng.gen_range(0.0..3000.0 / size);
+    let velocity = Velocity {
+        translation_speed: Vec3 {
+            x: direction.cos(),
+            y: direction.sin(),
+            z: 0.0,
+        } * speed,
+        rotation_speed: rng.gen_range(-100.0 / size..100.0 / size),
+    };
+    let outline = asteroid_outline(&mut WyRand::seed_from_u64(seed), size / 2.0);
+    spawn_asteroid_shape(
+        spawner,
+        settings,
+        Transform::from_translation(position),
+        AsteroidShape { outline, seed },
+        material,
+        velocity,
+    );
+}
+
+/// Spawns an asteroid with a given outline, centered on the transform
+fn spawn_asteroid_shape(
+    spawner: &mut Spawner,
+    settings: &AsteroidSettings,
+    transform: Transform,
+    shape: AsteroidShape,
+    material: AsteroidMaterial,
+    velocity: Velocity,
+) {
+    let outline = &shape.outline;
+    // Stats go by the diameter of a circle with the same area
+    let size = (polygon_area_centroid(outline).0 / PI).sqrt() * 2.0;
+    let reach = outline
+        .iter()
+        .map(|point| point.length())
+        .fold(0.0, f32::max);
+    let mut loot = vec![
+        (Loot::Repair(10.0), 0.1),
+        (Loot::HeatCapacity(10.0), 0.02),
+        (Loot::Cooling(5.0), 0.02),
+        (Loot::Ricochet(1), 0.01),
+    ];
+    if material == AsteroidMaterial::Crystal {
+        loot.push((Loot::Currency((size / 5.0).max(1.0) as u32), 1.0));
+    }
+    let mut asteroid = spawner.commands.spawn(AsteroidBundle {
+        collision: CollisionConfig {
+            radius: reach,
+            layers: settings.layers(),
+            ..Default::default()
+        },
+        hull: ConvexHull(convex_hull(outline)),
+        health: Health {
+            health: size / 2.0 * material.toughness(),
+            max: size / 2.0 * material.toughness(),
+        },
+        mesh_bundle: MaterialMesh2dBundle {
+            mesh: spawner.meshes.add(polygon_mesh(outline, reach)).into(),
+            material: spawner.materials.add(ColorMaterial {
+                color: Color::WHITE,
+                texture: Some(spawner.assets.load(material.texture())),
+            }),
+            transform,
+            ..Default::default()
+        },
+        shape,
+        velocity,
+        damage: Damage::Kinetic(size / 3.0),
+        knockback: Knockback(size * 2.0 * material.heft()),
+        mass: Mass(size.powi(2) / 1000.0 * material.heft()),
+        impact_damage: ImpactDamage(settings.impact_damage),
+        death_sound: DeathSound(spawner.assets.load("hit2.wav")),
+        score: ScoreValue((1000.0 / size) as u32),
+        loot: DropsLoot(loot),
+        fragments: material.fragments(),
+        material,
+        ..Default::default()
+    });
+    match material {
+        AsteroidMaterial::Metal => {
+            // Plated, so even big hits only chip off a bit at a time
+            asteroid.insert((
+                Resistances {
+                    kinetic: 0.3,
+                    ..Default::default()
+                },
+                DamageCap(size / 4.0),
+            ));
+        }
+        AsteroidMaterial::Explosive => {
+            asteroid.insert(ExplodesOnDeath(Explosion {
+                radius: size * 1.5,
+                damage: Damage::Explosive(size / 2.0),
+                knockback: size,
+                ..Default::default()
+            }));
+        }
+        _ => {}
+    }
+}
+
+fn spawn_asteroids(
+    mut spawner: Spawner,
+    mut rng: ResMut<GlobalEntropy<WyRand>>,
+    settings: Res<AsteroidSettings>,
+    mut run: ResMut<Run>,
+) {
+    for _ in 0..4 {
+        let size: f32 = rng.gen_range(10.0..100.0);
+        let position = Vec3::new(
+            rng.gen_range(-500.0..500.0), // TODO: set this from background
+            rng.gen_range(-500.0..500.0),
+            0.0,
+        );
+        let material = *ASTEROID_MATERIALS
+            .choose_weighted(&mut *rng, AsteroidMaterial::spawn_weight)
+            .unwrap_or(&AsteroidMaterial::Rock);
+        spawn_asteroid(
+            &mut spawner,
+            &settings,
+            &mut *rng,
+            position,
+            size,
+            material,
+            run.next_asteroid_seed(),
+        );
+    }
+}
+
+#[derive(Component)]
+struct Enemy;
+
+/// Every kind of enemy ship. Stats for each live in [`spawn_enemy`]
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+enum EnemyKind {
+    Fighter,
+    /// Flies straight into the player and blows up
+    Kamikaze,
+    /// Sits still and shoots
+    Turret,
+    /// Hangs back and leads its shots
+    Sniper,
+    /// Slow and tough, launches drones
+    Carrier,
+    /// Only comes from carriers
+    Drone,
+    /// Circles around leaving mines behind
+    MineLayer,
+    /// Only comes every few waves, see [`spawn_boss`]
+    Boss,
+}
+
+/// Everything that can show up when the field is populated
+const ENEMY_ROSTER: [EnemyKind; 6] = [
+    EnemyKind::Fighter,
+    EnemyKind::Kamikaze,
+    EnemyKind::Turret,
+    EnemyKind::Sniper,
+    EnemyKind::Carrier,
+    EnemyKind::MineLayer,
+];
+
+impl EnemyKind {
+    /// How likely this is to be picked compared to the others
+    fn spawn_weight(&self) -> f32 {
+        match self {
+            EnemyKind::Fighter => 5.0,
+            EnemyKind::Kamikaze => 3.0,
+            EnemyKind::Turret => 2.0,
+            EnemyKind::Sniper => 2.0,
+            EnemyKind::Carrier => 0.5,
+            EnemyKind::Drone => 0.0,
+            EnemyKind::MineLayer => 1.0,
+            EnemyKind::Boss => 0.0,
+        }
+    }
+
+    fn texture(&self) -> &'static str {
+        match self {
+            EnemyKind::Fighter => "enemy_fighter_100.png",
+            EnemyKind::Kamikaze => "enemy_kamikaze_100.png",
+            EnemyKind::Turret => "enemy_turret_100.png",
+            EnemyKind::Sniper => "enemy_sniper_100.png",
+            EnemyKind::Carrier => "enemy_carrier_100.png",
+            EnemyKind::Drone => "enemy_drone_100.png",
+            EnemyKind::MineLayer => "enemy_minelayer_100.png",
+            EnemyKind::Boss => "boss_hull_300.png",
+        }
+    }
+}
+
+#[derive(Bundle)]
+struct EnemyBundle {
+    sprite_bundle: SpriteBundle,
+    enemy: Enemy,
+    kind: EnemyKind,
+    velocity: Velocity,
+    drag: Drag,
+    wrap: Wrappable,
+    health: Health,
+    affiliation: Affiliation,
+    collision: CollisionConfig,
+    damage: Damage,
+    knockback: Knockback,
+    mass: Mass,
+    status_effects: StatusEffects,
+    steering: Steering,
+    death_sound: DeathSound,
+    score: ScoreValue,
+    loot: DropsLoot,
+}
+
+fn spawn_enemy(
+    commands: &mut Commands,
+    assets: &AssetServer,
+    kind: EnemyKind,
+    position: Vec3,
+) -> Entity {
+    let steering = Steering {
+        behavior: SteeringBehavior::Seek,
+        thrust: 600.0,
+        turn_rate: 3.0 * PI,
+        avoid_radius: 150.0,
+        engage_range: 500.0,
+        aim_tolerance: 0.2,
+        leads_shots: false,
+        flee_below: 0.25,
+    };
+    let gun = Weapon {
+        fire_rate: 1.5,
+        tint: Color::rgb(1.0, 0.4, 0.4),
+        pitch: 1.2,
+        ..Default::default()
+    };
+    // Size is the sprite size, the player is 100
+    let (size, health, tint, damage, mass, score, steering, weapon) = match kind {
+        EnemyKind::Fighter => (
+            100.0,
+            40.0,
+            Color::rgb(1.0, 0.4, 0.4),
+            Damage::Kinetic(20.0),
+            10.0,
+            50,
+            steering,
+            Some(Weapon {
+                fire_rate: 0.8,
+                pattern: FirePattern::Burst {
+                    shots: 3,
+                    interval: 0.12,
+                },
+                ..gun
+            }),
+        ),
+        EnemyKind::Kamikaze => (
+            60.0,
+            15.0,
+            Color::ORANGE,
+            Damage::Explosive(40.0),
+            5.0,
+            30,
+            Steering {
+                thrust: 1000.0,
+                avoid_radius: 60.0,
+                flee_below: 0.0,
+                ..steering
+            },
+            None,
+        ),
+        EnemyKind::Turret => (
+            110.0,
+            80.0,
+            Color::PURPLE,
+            Damage::Kinetic(20.0),
+            100.0,
+            60,
+            Steering {
+                behavior: SteeringBehavior::Hold,
+                thrust: 0.0,
+                engage_range: 700.0,
+                // Spirals go everywhere anyway
+                aim_tolerance: PI,
+                flee_below: 0.0,
+                ..steering
+            },
+            Some(Weapon {
+                fire_rate: 4.0,
+                count: 4,
+                pattern: FirePattern::Spiral { step: 0.25 },
+                projectile_speed: 300.0,
+                lifetime: 2.5,
+                tint: Color::PURPLE,
+                ..gun
+            }),
+        ),
+        EnemyKind::Sniper => (
+            90.0,
+            30.0,
+            Color::LIME_GREEN,
+            Damage::Kinetic(10.0),
+            8.0,
+            80,
+            Steering {
+                behavior: SteeringBehavior::KeepDistance { distance: 700.0 },
+                engage_range: 1000.0,
+                aim_tolerance: 0.05,
+                leads_shots: true,
+                ..steering
+            },
+            Some(Weapon {
+                fire_rate: 0.5,
+                projectile_speed: 900.0,
+                lifetime: 1.5,
+                damage: Damage::Kinetic(20.0),
+                inflicts: Inflicts(vec![(StatusEffect::Slow(0.5), 1.5)]),
+                tint: Color::LIME_GREEN,
+                pitch: 1.6,
+                ..gun
+            }),
+        ),
+        EnemyKind::Carrier => (
+            200.0,
+            150.0,
+            Color::GRAY,
+            Damage::Kinetic(30.0),
+            60.0,
+            150,
+            Steering {
+                behavior: SteeringBehavior::Orbit { radius: 600.0 },
+                thrust: 250.0,
+                turn_rate: PI,
+                engage_range: 800.0,
+                aim_tolerance: PI,
+                flee_below: 0.0,
+                ..steering
+            },
+            Some(Weapon {
+                fire_rate: 0.4,
+                count: 12,
+                pattern: FirePattern::Ring,
+                projectile_speed: 250.0,
+                lifetime: 3.0,
+                tint: Color::GRAY,
+                pitch: 0.7,
+                ..gun
+            }),
+        ),
+        EnemyKind::Drone => (
+            40.0,
+            8.0,
+            Color::SILVER,
+            Damage::Kinetic(5.0),
+            2.0,
+            5,
+            Steering {
+                thrust: 800.0,
+                avoid_radius: 80.0,
+                engage_range: 350.0,
+                flee_below: 0.0,
+                ..steering
+            },
+            Some(Weapon {
+                fire_rate: 1.0,
+                damage: Damage::Kinetic(3.0),
+                tint: Color::SILVER,
+                pitch: 1.5,
+                ..gun
+            }),
+        ),
+        EnemyKind::MineLayer => (
+            100.0,
+            35.0,
+            Color::YELLOW,
+            Damage::Kinetic(20.0),
+            10.0,
+            70,
+            Steering {
+                behavior: SteeringBehavior::Orbit { radius: 400.0 },
+                thrust: 400.0,
+                // Mines get dropped no matter which way it's facing
+                aim_tolerance: PI,
+                ..steering
+            },
+            Some(Weapon {
+                fire_rate: 0.3,
+                // AI holds the trigger down
+                automatic: true,
+                ..Weapon::mines()
+            }),
+        ),
+        EnemyKind::Boss => return spawn_boss(commands, assets, position),
+    };
+
+    let mut enemy = commands.spawn(EnemyBundle {
+        sprite_bundle: SpriteBundle {
+            texture: assets.load(kind.texture()),
+            transform: Transform::from_translation(position),
+            sprite: Sprite {
+                color: tint,
+                custom_size: Some(Vec2::splat(size)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        enemy: Enemy,
+        kind,
+        velocity: Velocity::default(),
+        drag: Drag {
+            translational: 1.5,
+            rotational: 2.0,
+        },
+        wrap: Wrappable,
+        health: Health {
+            health,
+            max: health,
+        },
+        affiliation: Affiliation::Hostile,
+        collision: CollisionConfig {
+            radius: size * 0.65,
+            layers: CollisionLayers {
+                membership: CollisionLayers::SHIP,
+                filter: CollisionLayers::ALL,
+            },
+            ..Default::default()
+        },
+        damage,
+        knockback: Knockback(size / 10.0),
+        mass: Mass(mass),
+        status_effects: StatusEffects::default(),
+        steering,
+        death_sound: DeathSound(assets.load("hit2.wav")),
+        score: ScoreValue(score),
+        loot: DropsLoot(match kind {
+            EnemyKind::Drone => vec![],
+            _ => vec![(Loot::Currency(score / 5), 0.5), (Loot::Repair(10.0), 0.2)],
+        }),
+    });
+    if let Some(weapon) = weapon {
+        enemy.insert(weapon);
+    }
+    match kind {
+        EnemyKind::Kamikaze => {
+            enemy.insert((
+                DiesOnContact,
+                ExplodesOnDeath(Explosion {
+                    radius: 120.0,
+                    damage: Damage::Explosive(20.0),
+                    knockback: 30.0,
+                    ..Default::default()
+                }),
+            ));
+        }
+        EnemyKind::Turret => {
+            // Armored, so it takes the same number of hits whatever it's shot with.
+            // The short invulnerability stops a lingering ram from counting every frame
+            enemy.insert((
+                HitCounter {
+                    remaining: 8,
+                    max: 8,
+                },
+                InvulnerableOnHit(0.1),
+            ));
+        }
+        EnemyKind::Carrier => {
+            enemy.insert(DroneBay {
+                timer: Timer::from_seconds(4.0, TimerMode::Repeating),
+                max: 4,
+            });
+        }
+        _ => {}
+    }
+    enemy.id()
+}
+
+/// Dies the first time it rams a hostile ship, like a kamikaze reaching its target
+#[derive(Component)]
+struct DiesOnContact;
+
+fn die_on_contact(
+    query: Query<(), (With<DiesOnContact>, Without<Dead>)>,
+    configs: Query<&CollisionConfig, Without<Projectile>>,
+    affiliations: Query<&Affiliation>,
+    relations: Res<FactionRelations>,
+    mut collisions: EventReader<CollisionEvent>,
+    mut died: EventWriter<Died>,
+    mut commands: Commands,
+) {
+    let mut rammed = Vec::new();
+    for collision in collisions.read() {
+        for i in 0..=1 {
+            let (entity, other) = (collision.entities[i], collision.entities[i.abs_diff(1)]);
+            if !query.contains(entity) || rammed.contains(&entity) {
+                continue;
+            }
+            // Only ramming another ship sets it off, anything else hurts it like normal.
+            // Lasers come from the ship firing them, but only a ram lets it hit back
+            let hit_ship = configs
+                .get(collision.parts[i.abs_diff(1)])
+                .map_or(false, |config| {
+                    config.layers.membership & CollisionLayers::SHIP != 0
+                });
+            if !hit_ship || collision.damage[i].is_none() {
+                continue;
+            }
+            let relationship = relations.between(
+                affiliations.get(entity).ok().copied(),
+                affiliations.get(other).ok().copied(),
+            );
+            if !relationship.damages() {
+                continue;
+            }
+            rammed.push(entity);
+            commands.entity(entity).insert(Dead);
+            died.send(Died {
+                entity,
+                cause: DeathCause::Spent,
+            });
+        }
+    }
+}
+
+/// Spawn `count` enemies picked by spawn weight, kept away from the middle where the player starts
+/// A random spot in the world at least `clearance` away from the player, counting across the wrap
+fn enemy_spawn_position(
+    rng: &mut impl Rng,
+    player: Vec2,
+    world_size: Vec2,
+    clearance: f32,
+) -> Vec3 {
+    let half_size = world_size / 2.0;
+    (0..20)
+        .map(|_| {
+            Vec2::new(
+                rng.gen_range(-half_size.x..half_size.x),
+                rng.gen_range(-half_size.y..half_size.y),
+            )
+        })
+        .find(|candidate| wrapped_offset(player, *candidate, world_size).length() >= clearance)
+        // The far side of the world from the player
+        .unwrap_or_else(|| wrapped_offset(Vec2::ZERO, player + half_size, world_size))
+        .extend(0.0)
+}
+
+fn populate_enemies(
+    commands: &mut Commands,
+    assets: &AssetServer,
+    rng: &mut impl Rng,
+    count: usize,
+    player: Vec2,
+    world_size: Vec2,
+) {
+    for _ in 0..count {
+        let Ok(kind) = ENEMY_ROSTER.choose_weighted(rng, EnemyKind::spawn_weight) else {
+            return;
+        };
+        let position = enemy_spawn_position(rng, player, world_size, 350.0);
+        spawn_enemy(commands, assets, *kind, position);
+    }
+}
+
+/// Progress through the waves of enemies in a run
+#[derive(Resource)]
+struct Waves {
+    /// The wave currently being fought, 0 before the first
+    number: u32,
+    /// Breather between clearing a wave and the next one coming in
+    next: Timer,
+}
+impl Default for Waves {
+    fn default() -> Self {
+        Waves {
+            number: 0,
+            next: Timer::from_seconds(3.0, TimerMode::Once),
+        }
+    }
+}
+
+/// Every this many waves is a boss instead of a normal wave
+const BOSS_WAVE_EVERY: u32 = 5;
+
+fn run_waves(
+    enemies: Query<(), With<Enemy>>,
+    players: Query<&Transform, With<Player>>,
+    background: Query<&Background>,
+    mut waves: ResMut<Waves>,
+    time: Res<Time>,
+    mut rng: ResMut<GlobalEntropy<WyRand>>,
+    mut spawner: Spawner,
+) {
+    if !enemies.is_empty() || !waves.next.tick(time.delta()).finished() {
+        return;
+    }
+    waves.next.reset();
+    waves.number += 1;
+    // Kept away from wherever the player is now, or the middle while they're respawning
+    let player = players
+        .get_single()
+        .map_or(Vec2::ZERO, |transform| transform.translation.truncate());
+    let world_size = background.single().size;
+    if waves.number % BOSS_WAVE_EVERY == 0 {
+        let position = enemy_spawn_position(&mut *rng, player, world_size, 500.0);
+        spawn_enemy(
+            &mut spawner.commands,
+            &spawner.assets,
+            EnemyKind::Boss,
+            position,
+        );
+    } else {
+        populate_enemies(
+            &mut spawner.commands,
+            &spawner.assets,
+            &mut *rng,
+            2 + waves.number as usize,
+            player,
+            world_size,
+        );
+    }
+}
+
+#[derive(Component)]
+struct Boss;
+
+/// Health fractions at which the boss moves on to its next phase, highest first
+#[derive(Component)]
+struct BossPhases {
+    thresholds: Vec<f32>,
+    current: usize,
+}
+
+/// A destructible part of a boss. The boss's hull loses this much [`Armor`] when it's destroyed
+#[derive(Component)]
+struct WeakPoint {
+    armor: f32,
+}
+
+/// How the boss flies and fights in each phase, along with its tint
+fn boss_phase(phase: usize) -> (Steering, Weapon, Color) {
+    let steering = Steering {
+        behavior: SteeringBehavior::KeepDistance { distance: 500.0 },
+        thrust: 200.0,
+        turn_rate: PI,
+        avoid_radius: 100.0,
+        engage_range: 900.0,
+        aim_tolerance: 0.3,
+        leads_shots: false,
+        flee_below: 0.0,
+    };
+    match phase {
+        // Hangs back firing spreads
+        0 => (
+            steering,
+            Weapon {
+                fire_rate: 3.0,
+                count: 3,
+                spread: 0.3,
+                pattern: FirePattern::Wave {
+                    amplitude: 0.6,
+                    step: 0.5,
+                },
+                heat: Heat {
+                    per_shot: 0.0,
+                    ..Default::default()
+                },
+                // Burn rounds
+                inflicts: Inflicts(vec![(StatusEffect::Burn(2.0), 3.0)]),
+                tint: Color::rgb(1.0, 0.3, 0.3),
+                pitch: 0.8,
+                ..Default::default()
+            },
+            Color::rgb(0.8, 0.5, 0.5),
+        ),
+        // Circles while its drones come out and mines get left behind
+        1 => (
+            Steering {
+                behavior: SteeringBehavior::Orbit { radius: 450.0 },
+                thrust: 300.0,
+                aim_tolerance: PI,
+                ..steering
+            },
+            Weapon {
+                fire_rate: 0.5,
+                automatic: true,
+                ..Weapon::mines()
+            },
+            Color::rgb(0.9, 0.35, 0.35),
+        ),
+        // Comes straight for the player with missiles
+        _ => (
+            Steering {
+                behavior: SteeringBehavior::Seek,
+                thrust: 450.0,
+                turn_rate: 2.0 * PI,
+                aim_tolerance: PI / 3.0,
+                ..steering
+            },
+            Weapon {
+                fire_rate: 1.0,
+                heat: Heat {
+                    per_shot: 0.0,
+                    ..Default::default()
+                },
+                ..Weapon::missiles()
+            },
+            Color::rgb(1.0, 0.2, 0.2),
+        ),
+    }
+}
+
+fn spawn_boss(commands: &mut Commands, assets: &AssetServer, position: Vec3) -> Entity {
+    let (steering, weapon, tint) = boss_phase(0);
+    let weak_points = [
+        Vec3::new(-110.0, -60.0, 1.0),
+        Vec3::new(110.0, -60.0, 1.0),
+        Vec3::new(0.0, 120.0, 1.0),
+    ];
+    let weak_point_armor = 2.0;
+    // Just for show, tucked under the back of the hull
+    let engines = [
+        Vec3::new(-45.0, -140.0, -1.0),
+        Vec3::new(45.0, -140.0, -1.0),
+    ];
+    commands
+        .spawn((
+            EnemyBundle {
+                sprite_bundle: SpriteBundle {
+                    texture: assets.load(EnemyKind::Boss.texture()),
+                    transform: Transform::from_translation(position),
+                    sprite: Sprite {
+                        color: tint,
+                        custom_size: Some(Vec2::splat(300.0)),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                enemy: Enemy,
+                kind: EnemyKind::Boss,
+                velocity: Velocity::default(),
+                drag: Drag {
+                    translational: 1.5,
+                    rotational: 2.0,
+                },
+                wrap: Wrappable,
+                health: Health {
+                    health: 600.0,
+                    max: 600.0,
+                },
+                affiliation: Affiliation::Hostile,
+                collision: CollisionConfig {
+                    radius: 110.0,
+                    layers: CollisionLayers {
+                        membership: CollisionLayers::SHIP,
+                        filter: CollisionLayers::ALL,
+                    },
+                    ..Default::default()
+                },
+                damage: Damage::Kinetic(40.0),
+                knockback: Knockback(30.0),
+                mass: Mass(200.0),
+                status_effects: StatusEffects::default(),
+                steering,
+                death_sound: DeathSound(assets.load("hit2.wav")),
+                score: ScoreValue(1000),
+                // Always pays out
+                loot: DropsLoot(vec![
+                    (Loot::Currency(200), 1.0),
+                    (Loot::Repair(50.0), 1.0),
+                    (Loot::HeatCapacity(20.0), 1.0),
+                    (Loot::Cooling(10.0), 1.0),
+                    (Loot::Pierce(1), 1.0),
+                    (Loot::Split(2), 1.0),
+                ]),
+            },
+            weapon,
+            Boss,
+            BossPhases {
+                thresholds: vec![0.66, 0.33],
+                current: 0,
+            },
+            // The hull shrugs off most hits until the weak points are gone
+            Armor(weak_point_armor * weak_points.len() as f32),
+            ExplodesOnDeath(Explosion {
+                radius: 300.0,
+                damage: Damage::Explosive(30.0),
+                knockback: 60.0,
+                ..Default::default()
+            }),
+        ))
+        .with_children(|parent| {
+            for offset in weak_points {
+                parent.spawn((
+                    WeakPoint {
+                        armor: weak_point_armor,
+                    },
+                    SpriteBundle {
+                        texture: assets.load("boss_weak_point_60.png"),
+                        transform: Transform::from_translation(offset),
+                        sprite: Sprite {
+                            color: Color::YELLOW,
+                            custom_size: Some(Vec2::splat(60.0)),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    Health {
+                        health: 60.0,
+                        max: 60.0,
+                    },
+                    Affiliation::Hostile,
+                    CollisionConfig {
+                        radius: 30.0,
+                        layers: CollisionLayers {
+                            membership: CollisionLayers::SHIP,
+                            filter: CollisionLayers::ALL,
+                        },
+                        ..Default::default()
+                    },
+                    DeathSound(assets.load("hit2.wav")),
+                    ScoreValue(100),
+                ));
+            }
+            for offset in engines {
+                parent.spawn(SpriteBundle {
+                    texture: assets.load("boss_engine_60.png"),
+                    transform: Transform::from_translation(offset),
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::splat(60.0)),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                });
+            }
+        })
+        .id()
+}
+
+fn advance_boss_phases(
+    mut bosses: Query<
+        (
+            Entity,
+            &Health,
+            &mut BossPhases,
+            &mut Steering,
+            &mut Weapon,
+            &mut Sprite,
+        ),
+        With<Boss>,
+    >,
+    mut commands: Commands,
+) {
+    for (boss, health, mut phases, mut steering, mut weapon, mut sprite) in bosses.iter_mut() {
+        let fraction = health.health / health.max;
+        let phase = phases
+            .thresholds
+            .iter()
+            .filter(|threshold| fraction < **threshold)
+            .count();
+        if phase == phases.current {
+            continue;
+        }
+        phases.current = phase;
+        (*steering, *weapon, sprite.color) = boss_phase(phase);
+        if phase == 1 {
+            commands.entity(boss).insert(DroneBay {
+                timer: Timer::from_seconds(3.0, TimerMode::Repeating),
+                max: 3,
+            });
+        }
+    }
+}
+
+fn break_weak_points(
+    weak_points: Query<(&WeakPoint, &Parent)>,
+    mut armor: Query<&mut Armor>,
+    mut died: EventReader<Died>,
+) {
+    for death in died.read() {
+        let Ok((weak_point, parent)) = weak_points.get(death.entity) else {
+            continue;
+        };
+        if let Ok(mut armor) = armor.get_mut(parent.get()) {
+            armor.0 = (armor.0 - weak_point.armor).max(0.0);
+        }
+    }
+}
+
+#[derive(Component)]
+struct BossBar;
+
+fn update_boss_bar(
+    bosses: Query<&Health, With<Boss>>,
+    mut root: Query<&mut Visibility, With<BossBar>>,
+    mut bars: Query<(&mut Style, &UiBar)>,
+) {
+    let boss = bosses.iter().next();
+    for mut visibility in root.iter_mut() {
+        *visibility = if boss.is_some() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+    let Some(boss) = boss else {
+        return;
+    };
+    for (mut style, bar) in bars.iter_mut() {
+        style.width = match bar {
+            UiBar::BossBack => Val::Px(BOSS_BAR_WIDTH),
+            UiBar::BossFront => Val::Px(BOSS_BAR_WIDTH * (boss.health / boss.max).max(0.0)),
+            _ => continue,
+        };
+    }
+}
+
+const BOSS_BAR_WIDTH: f32 = 600.0;
+
+/// Launches drones every time the timer goes off, as long as it doesn't already have `max` out
+#[derive(Component)]
+struct DroneBay {
+    timer: Timer,
+    max: u32,
+}
+
+/// Which carrier a drone came from
+#[derive(Component)]
+struct LaunchedBy(Entity);
+
+fn launch_drones(
+    mut carriers: Query<(Entity, &Transform, &mut DroneBay)>,
+    drones: Query<&LaunchedBy>,
+    time: Res<Time>,
+    mut commands: Commands,
+    assets: Res<AssetServer>,
+) {
+    for (carrier, transform, mut bay) in carriers.iter_mut() {
+        if !bay.timer.tick(time.delta()).just_finished()
+            || drones
+                .iter()
+                .filter(|launched_by| launched_by.0 == carrier)
+                .count()
+                >= bay.max as usize
+        {
+            continue;
+        }
+        let drone = spawn_enemy(
+            &mut commands,
+            &assets,
+            EnemyKind::Drone,
+            transform.translation,
+        );
+        commands.entity(drone).insert(LaunchedBy(carrier));
+    }
+}
+
+/// How an AI ship flies. Controls the ship the same way the player does, through thrust and turning
+#[derive(Component, Clone)]
+struct Steering {
+    behavior: SteeringBehavior,
+    /// Forward acceleration at full throttle
+    thrust: f32,
+    /// Angular acceleration when turning as hard as possible
+    turn_rate: f32,
+    /// Extra room kept between the ship and the edge of asteroids
+    avoid_radius: f32,
+    /// Distance at which it starts shooting at the player
+    engage_range: f32,
+    /// How far off in radians its aim can be and still shoot
+    aim_tolerance: f32,
+    /// Aims where the player will be instead of where they are
+    leads_shots: bool,
+    /// Fraction of health below which it runs away no matter the behavior
+    flee_below: f32,
+}
+
+#[derive(Clone, Copy)]
+enum SteeringBehavior {
+    /// Straight at the player
+    Seek,
+    /// Straight away from the player
+    Flee,
+    /// Circle the player at a distance
+    Orbit { radius: f32 },
+    /// Stay put and keep facing the player
+    Hold,
+    /// Back off or close in to stay at a distance while facing the player
+    KeepDistance { distance: f32 },
+}
+
+impl SteeringBehavior {
+    /// Whether the ship points at the player and moves forward and backward instead of pointing where it's going
+    fn faces_player(&self) -> bool {
+        matches!(
+            self,
+            SteeringBehavior::Hold | SteeringBehavior::KeepDistance { .. }
+        )
+    }
+}
+
+fn steer_enemies(
+    mut enemies: Query<
+        (
+            &Steering,
+            &mut Velocity,
+            &Transform,
+            &Health,
+            Option<&StatusEffects>,
+            Option<&mut Weapon>,
+        ),
+        With<Enemy>,
+    >,
+    player: Query<(&Transform, &Velocity), (With<Player>, Without<Enemy>)>,
+    asteroids: Query<(&Transform, &CollisionConfig), With<Asteroid>>,
+    background: Query<&Background>,
+    time: Res<Time>,
+) {
+    let world_size = background.single().size;
+    let player = player.get_single().ok().map(|(transform, velocity)| {
+        (
+            transform.translation.truncate(),
+            velocity.translation_speed.truncate(),
+        )
+    });
+    for (steering, mut velocity, transform, health, status_effects, weapon) in enemies.iter_mut() {
+        let position = transform.translation.truncate();
+        let forward = transform.local_y().truncate();
+        let mut desired = Vec2::ZERO;
+        let mut aim = None;
+
+        if let Some((player, player_velocity)) = player {
+            let offset = wrapped_offset(position, player, world_size);
+            let distance = offset.length();
+            let toward = offset.normalize_or_zero();
+            let fleeing = health.health < health.max * steering.flee_below;
+            desired = match steering.behavior {
+                _ if fleeing => -toward,
+                SteeringBehavior::Seek => toward,
+                SteeringBehavior::Flee => -toward,
+                SteeringBehavior::Orbit { radius } => {
+                    // Go around while drifting in or out toward the right distance
+                    toward.perp() + toward * ((distance - radius) / radius).clamp(-1.0, 1.0)
+                }
+                SteeringBehavior::Hold => Vec2::ZERO,
+                SteeringBehavior::KeepDistance { distance: keep } => {
+                    toward * ((distance - keep) / keep).clamp(-1.0, 1.0)
+                }
+            };
+            if distance < steering.engage_range {
+                // Shots carry the shooter's velocity, so lead by how the player moves relative to it
+                let lead = match &weapon {
+                    Some(weapon) if steering.leads_shots => {
+                        (player_velocity - velocity.translation_speed.truncate()) * distance
+                            / weapon.projectile_speed
+                    }
+                    _ => Vec2::ZERO,
+                };
+                aim = Some(offset + lead);
+            }
+        }
+
+        // Push away from asteroids harder the closer they are
+        for (asteroid, collision) in asteroids.iter() {
+            let offset = wrapped_offset(position, asteroid.translation.truncate(), world_size);
+            let reach = steering.avoid_radius + collision.radius;
+            let distance = offset.length();
+            if distance < reach {
+                desired -= offset.normalize_or_zero() * (1.0 - distance / reach) * 2.0;
+            }
+        }
+
+        if let Some(mut weapon) = weapon {
+            weapon.trigger = aim.map_or(false, |aim| {
+                forward.angle_between(aim).abs() < steering.aim_tolerance
+            });
+        }
+        if status_effects.map_or(false, |status_effects| {
+            status_effects.has(StatusEffect::Stun)
+        }) {
+            continue;
+        }
+        let facing = if steering.behavior.faces_player() {
+            aim.unwrap_or(desired)
+        } else {
+            desired
+        };
+        if facing != Vec2::ZERO {
+            let turn = forward.angle_between(facing);
+            velocity.rotation_speed +=
+                turn.clamp(-1.0, 1.0) * steering.turn_rate * time.delta_seconds();
+        }
+        let throttle = if steering.behavior.faces_player() {
+            // Can back up like the player
+            desired.dot(forward).clamp(-1.0, 1.0)
+        } else if forward.angle_between(desired).abs() < PI / 2.0 {
+            // Only thrust once it's roughly facing the right way
+            desired.length().min(1.0)
+        } else {
+            0.0
+        };
+        velocity.translation_speed +=
+            (forward * steering.thrust * throttle * time.delta_seconds()).extend(0.0);
+    }
+}
+
+// ============================================================= Ripped from book fps counter
+
+/// Marker to find the container entity so we can show/hide the FPS counter
+#[derive(Component)]
+struct FpsRoot;
+
+/// Marker to find the text entity so we can update it
+#[derive(Component)]
+struct FpsText;
+
+fn setup_fps_counter(mut commands: Commands, main_camera: Query<Entity, With<MainCamera>>) {
+    // create our UI root node
+    // this is the wrapper/container for the text
+    // TODO: single can panic
+    let root = commands
+        .spawn((
+            TargetCamera(main_camera.single()),
+            FpsRoot,
+            NodeBundle {
+                // give it a dark background for readability
+                background_color: BackgroundColor(Color::BLACK.with_a(0.5)),
+                // make it "always on top" by setting the Z index to maximum
+                // we want it to be displayed over all other UI
+                z_index: ZIndex::Global(i32::MAX),
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    // position it at the top-right corner
+                    // 1% away from the top window edge
+                    right: Val::Percent(1.),
+                    top: Val::Percent(1.),
+                    // set bottom/left to Auto, so it can be
+                    // automatically sized depending on the text
+                    bottom: Val::Auto,
+                    left: Val::Auto,
+                    // give it some padding for readability
+                    padding: UiRect::all(Val::Px(4.0)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ))
+        .id();
+    // create our text
+    let text_fps = commands
+        .spawn((
+            FpsText,
+            TextBundle {
+                // use two sections, so it is easy to update just the number
+                text: Text::from_sections([
+                    TextSection {
+                        value: "FPS: ".into(),
+                        style: TextStyle {
+                            font_size: 16.0,
+                            color: Color::WHITE,
+                            // if you want to use your game's font asset,
+                            // uncomment this and provide the handle:
+                            // font: my_font_handle
+                            ..default()
+                        },
+                    },
+                    TextSection {
+                        value: " N/A".into(),
+                        style: TextStyle {
+                            font_size: 16.0,
+                            color: Color::WHITE,
+                            // if you want to use your game's font asset,
+                            // uncomment this and provide the handle:
+                            // font: my_font_handle
+                            ..default()
+                        },
+                    },
+                ]),
+                ..Default::default()
+            },
+        ))
+        .id();
+    commands.entity(root).push_children(&[text_fps]);
+}
+
+fn fps_text_update_system(
+    diagnostics: Res<DiagnosticsStore>,
+    mut query: Query<&mut Text, With<FpsText>>,
+) {
+    for mut text in &mut query {
+        // try to get a "smoothed" FPS value from Bevy
+        if let Some(value) = diagnostics
+            .get(&FrameTimeDiagnosticsPlugin::FPS)
+            .and_then(|fps| fps.smoothed())
+        {
+            // Format the number as to leave space for 4 digits, just in case,
+            // right-aligned and rounded. This helps readability when the
+            // number changes rapidly.
+            text.sections[1].value = format!("{value:>4.0}");
+
+            // Let's make it extra fancy by changing the color of the
+            // text according to the FPS value:
+            text.sections[1].style.color = if value >= 120.0 {
+                // Above 120 FPS, use green color
+                Color::rgb(0.0, 1.0, 0.0)
+            } else if value >= 60.0 {
+                // Between 60-120 FPS, gradually transition from yellow to green
+                Color::rgb((1.0 - (value - 60.0) / (120.0 - 60.0)) as f32, 1.0, 0.0)
+            } else if value >= 30.0 {
+                // Between 30-60 FPS, gradually transition from red to yellow
+                Color::rgb(1.0, ((value - 30.0) / (60.0 - 30.0)) as f32, 0.0)
+            } else {
+                // Below 30 FPS, use red color
+                Color::rgb(1.0, 0.0, 0.0)
+            }
+        } else {
+            // display "N/A" if we can't get a FPS measurement
+            // add an extra space to preserve alignment
+            text.sections[1].value = " N/A".into();
+            text.sections[1].style.color = Color::WHITE;
+        }
+    }
+}
+
+/// Toggle the FPS counter when pressing F12
+fn fps_counter_showhide(
+    mut q: Query<&mut Visibility, With<FpsRoot>>,
+    kbd: Res<ButtonInput<KeyCode>>,
+) {
+    if kbd.just_pressed(KeyCode::F12) {
+        let mut vis = q.single_mut();
+        *vis = match *vis {
+            Visibility::Hidden => Visibility::Visible,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+#[derive(Component, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default, Debug)]
+enum Affiliation {
+    // should this just be part of collision config?
+    Friendly,
+    #[default]
+    Neutral,
+    Hostile,
+    /// Any factions beyond the main three, like rival enemy groups
+    Other(u8),
+}
+
+/// How two factions treat each other when they touch
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Relationship {
+    /// Bump into and hurt each other
+    Hostile,
+    /// Bump into each other, but only hurt each other through [`ImpactDamage`]
+    Neutral,
+    /// Pass through each other unharmed
+    Ally,
+    /// Never even checked against each other
+    Ignore,
+    /// For anything the others don't cover, like friendly fire that doesn't push teammates around
+    Custom { collide: bool, damage: bool },
+}
+impl Relationship {
+    /// Whether the pair physically push each other apart
+    fn collides(&self) -> bool {
+        match self {
+            Relationship::Hostile | Relationship::Neutral => true,
+            Relationship::Ally | Relationship::Ignore => false,
+            Relationship::Custom { collide, .. } => *collide,
+        }
+    }
+
+    /// Whether the pair deal their [`Damage`] to each other
+    fn damages(&self) -> bool {
+        match self {
+            Relationship::Hostile => true,
+            Relationship::Neutral | Relationship::Ally | Relationship::Ignore => false,
+            Relationship::Custom { damage, .. } => *damage,
+        }
+    }
+
+    /// Whether sensors pick the pair up at all
+    fn notices(&self) -> bool {
+        *self != Relationship::Ignore
+    }
+}
+
+/// Table of how every pair of factions treat each other. Order within a pair doesn't matter.
+#[derive(Resource)]
+struct FactionRelations {
+    relations: HashMap<(Affiliation, Affiliation), Relationship>,
+    /// Used for any pair not in the table
+    default: Relationship,
+}
+impl FactionRelations {
+    fn key(a: Affiliation, b: Affiliation) -> (Affiliation, Affiliation) {
+        (a.min(b), a.max(b))
+    }
+
+    fn set(&mut self, a: Affiliation, b: Affiliation, relationship: Relationship) {
+        self.relations.insert(Self::key(a, b), relationship);
+    }
+
+    fn get(&self, a: Affiliation, b: Affiliation) -> Relationship {
+        *self
+            .relations
+            .get(&Self::key(a, b))
+            .unwrap_or(&self.default)
+    }
+
+    /// Same as [`FactionRelations::get`], except anything without a faction runs into everything
+    fn between(&self, a: Option<Affiliation>, b: Option<Affiliation>) -> Relationship {
+        match (a, b) {
+            (Some(a), Some(b)) => self.get(a, b),
+            _ => Relationship::Hostile,
+        }
+    }
+}
+impl Default for FactionRelations {
+    fn default() -> Self {
+        // Everyone fights everyone except their own side.
+        // Asteroids and other neutral things just bounce off each other
+        let mut relations = FactionRelations {
+            relations: HashMap::new(),
+            default: Relationship::Hostile,
+        };
+        relations.set(
+            Affiliation::Friendly,
+            Affiliation::Friendly,
+            Relationship::Ally,
+        );
+        relations.set(
+            Affiliation::Neutral,
+            Affiliation::Neutral,
+            Relationship::Neutral,
+        );
+        relations.set(
+            Affiliation::Hostile,
+            Affiliation::Hostile,
+            Relationship::Ally,
+        );
+        relations
+    }
+}
+
+#[derive(Component)]
+struct Projectile;
+
+#[derive(Component, Clone, Debug)]
+enum Damage {
+    // should this just be part of collision config?
+    /// Untyped damage, only reduced by [`Armor`]
+    Basic(f32),
+    Kinetic(f32),
+    Explosive(f32),
+    Energy(f32),
+    /// Ignores all resistances and armor
+    True(f32),
+}
+impl Default for Damage {
+    fn default() -> Self {
+        Damage::Basic(0.0)
+    }
+}
+impl Damage {
+    fn scaled(&self, factor: f32) -> Damage {
+        match self {
+            Damage::Basic(amount) => Damage::Basic(amount * factor),
+            Damage::Kinetic(amount) => Damage::Kinetic(amount * factor),
+            Damage::Explosive(amount) => Damage::Explosive(amount * factor),
+            Damage::Energy(amount) => Damage::Energy(amount * factor),
+            Damage::True(amount) => Damage::True(amount * factor),
+        }
+    }
+
+    /// How much health this actually takes off after resistances and armor
+    fn against(&self, resistances: Option<&Resistances>, armor: Option<&Armor>) -> f32 {
+        let resistances = resistances.cloned().unwrap_or_default();
+        let (amount, resistance) = match self {
+            Damage::Basic(amount) => (*amount, 0.0),
+            Damage::Kinetic(amount) => (*amount, resistances.kinetic),
+            Damage::Explosive(amount) => (*amount, resistances.explosive),
+            Damage::Energy(amount) => (*amount, resistances.energy),
+            Damage::True(amount) => return *amount,
+        };
+        (amount * (1.0 - resistance) - armor.map_or(0.0, |armor| armor.0)).max(0.0)
+    }
+}
+
+/// Fraction of each type of damage ignored. 1.0 is immune, negative takes extra
+#[derive(Component, Clone, Default, Debug)]
+struct Resistances {
+    kinetic: f32,
+    explosive: f32,
+    energy: f32,
+}
+
+/// Flat amount taken off every hit after resistances, except true damage
+#[derive(Component, Default, Debug)]
+struct Armor(f32);
+
+#[derive(Event)]
+/// Health was taken off something
+struct DamageDealt {
+    target: Entity,
+    /// None for damage that doesn't come from a hit, like burning
+    source: Option<Entity>,
+    /// What was dealt before resistances and armor
+    damage: Damage,
+    /// What actually came off the target's health
+    amount: f32,
+    /// What the target's shield soaked up before it got to health
+    shielded: f32,
+    /// Whether this was the hit that took the target's health to 0
+    fatal: bool,
+}
+
+/// Soaks up damage before [`Health`], recharging after a while without being hit
+#[derive(Component)]
+struct Shield {
+    current: f32,
+    capacity: f32,
+    /// How long after a hit before recharging starts
+    regen_delay: Timer,
+    /// Per second once recharging
+    regen_rate: f32,
+}
+impl Shield {
+    /// Soaks up as much as it can, returning what's left over for health
+    fn absorb(&mut self, amount: f32) -> f32 {
+        let absorbed = amount.min(self.current);
+        self.current -= absorbed;
+        self.regen_delay.reset();
+        amount - absorbed
+    }
+}
+
+fn regenerate_shields(mut query: Query<&mut Shield>, time: Res<Time>) {
+    for mut shield in query.iter_mut() {
+        shield.regen_delay.tick(time.delta());
+        if shield.regen_delay.finished() && shield.current < shield.capacity {
+            shield.current =
+                (shield.current + shield.regen_rate * time.delta_seconds()).min(shield.capacity);
+        }
+    }
+}
+
+/// The visible bubble around a shielded parent, fading with charge and flashing when hit
+#[derive(Component)]
+struct ShieldBubble {
+    flash: Timer,
+}
+
+fn update_shield_bubbles(
+    mut bubbles: Query<(&Parent, &mut ShieldBubble, &Handle<ColorMaterial>)>,
+    shields: Query<&Shield>,
+    mut damage_dealt: EventReader<DamageDealt>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    time: Res<Time>,
+) {
+    let hits: Vec<Entity> = damage_dealt
+        .read()
+        .filter(|hit| hit.shielded > 0.0)
+        .map(|hit| hit.target)
+        .collect();
+    for (parent, mut bubble, material) in bubbles.iter_mut() {
+        let Ok(shield) = shields.get(parent.get()) else {
+            continue;
+        };
+        if hits.contains(&parent.get()) {
+            bubble.flash.reset();
+        }
+        bubble.flash.tick(time.delta());
+        if let Some(material) = materials.get_mut(material) {
+            let charge = shield.current / shield.capacity.max(f32::EPSILON);
+            let flash = bubble.flash.fraction_remaining();
+            material.color.set_a(0.15 * charge + 0.6 * flash);
+        }
+    }
+}
+
+/// Ignores collision damage while this lasts, blinking to show it. Knockback still applies
+#[derive(Component)]
+struct Invulnerable {
+    time: Timer,
+    blink: Timer,
+}
+impl Invulnerable {
+    fn new(seconds: f32) -> Self {
+        Invulnerable {
+            time: Timer::from_seconds(seconds, TimerMode::Once),
+            blink: Timer::from_seconds(0.1, TimerMode::Repeating),
+        }
+    }
+}
+
+/// Seconds of [`Invulnerable`] given whenever health is lost
+#[derive(Component)]
+struct InvulnerableOnHit(f32);
+
+fn tick_invulnerability(
+    mut query: Query<(Entity, &mut Invulnerable, &mut Visibility)>,
+    mut commands: Commands,
+    time: Res<Time>,
+) {
+    for (entity, mut invulnerable, mut visibility) in query.iter_mut() {
+        invulnerable.time.tick(time.delta());
+        if invulnerable.time.finished() {
+            *visibility = Visibility::Inherited;
+            commands.entity(entity).remove::<Invulnerable>();
+            continue;
+        }
+        if invulnerable.blink.tick(time.delta()).just_finished() {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Inherited,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
+
+/// The one place collision damage gets taken off health
+fn apply_damage(
+    mut query: Query<(
+        &mut Health,
+        Option<&Resistances>,
+        Option<&Armor>,
+        Option<&mut StatusEffects>,
+        Option<&mut Shield>,
+        Option<&DamageCap>,
+        Option<&mut HitCounter>,
+    )>,
+    invulnerability: Query<(Has<Invulnerable>, Option<&InvulnerableOnHit>)>,
+    mut collisions: EventReader<CollisionEvent>,
+    mut damage_dealt: EventWriter<DamageDealt>,
+    mut commands: Commands,
+) {
+    // Invulnerable only gets added at the end of the frame, so anything hit this frame is tracked here
+    let mut made_invulnerable = Vec::new();
+    for collision in collisions.read() {
+        for i in 0..=1 {
+            // Parts with their own health, like weak points, take the hit instead of their body
+            let target = if query.contains(collision.parts[i]) {
+                collision.parts[i]
+            } else {
+                collision.entities[i]
+            };
+            if let Ok((
+                mut health,
+                resistances,
+                armor,
+                status_effects,
+                shield,
+                damage_cap,
+                hit_counter,
+            )) = query.get_mut(target)
+            {
+                let Some(damage) = &collision.damage[i.abs_diff(1)] else {
+                    continue;
+                };
+                let (invulnerable, invulnerable_on_hit) =
+                    invulnerability.get(target).unwrap_or((false, None));
+                if invulnerable || made_invulnerable.contains(&target) {
+                    continue;
+                }
+                if let (Some(mut status_effects), Some(inflicts)) =
+                    (status_effects, &collision.inflicts[i.abs_diff(1)])
+                {
+                    for (effect, duration) in inflicts.0.iter() {
+                        status_effects.apply(*effect, *duration);
+                    }
+                }
+                let mitigated = damage
+                    .against(resistances, armor)
+                    .min(damage_cap.map_or(f32::INFINITY, |cap| cap.0));
+                // Shielded hits still count, otherwise anything left touching drains the shield every frame
+                if let (Some(invulnerable_on_hit), true) = (invulnerable_on_hit, mitigated > 0.0) {
+                    made_invulnerable.push(target);
+                    commands
+                        .entity(target)
+                        .insert(Invulnerable::new(invulnerable_on_hit.0));
+                }
+                let mut amount = shield.map_or(mitigated, |mut shield| shield.absorb(mitigated));
+                if let Some(mut hit_counter) = hit_counter {
+                    // Anything that gets through at all counts as exactly one hit
+                    if amount > 0.0 {
+                        hit_counter.remaining = hit_counter.remaining.saturating_sub(1);
+                        amount = health.health
+                            - health.max * hit_counter.remaining as f32
+                                / hit_counter.max.max(1) as f32;
+                    }
+                }
+                let was_alive = health.health > 0.0;
+                health.health -= amount;
+                damage_dealt.send(DamageDealt {
+                    target,
+                    source: Some(collision.entities[i.abs_diff(1)]),
+                    damage: damage.clone(),
+                    amount,
+                    shielded: mitigated - amount,
+                    fatal: was_alive && health.health <= 0.0,
+                });
+            }
+        }
+    }
+}
+
+/// Lingering effects that can ride along with [`Damage`] through [`Inflicts`]
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum StatusEffect {
+    /// Damage per second for each stack
+    Burn(f32),
+    /// Multiplier on how far something's velocity moves it
+    Slow(f32),
+    /// Can't be controlled at all
+    Stun,
+    /// Can't fire
+    Emp,
+}
+impl StatusEffect {
+    fn same_kind(&self, other: &StatusEffect) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+
+    fn tint(&self) -> Color {
+        match self {
+            StatusEffect::Burn(_) => Color::rgb(1.0, 0.5, 0.2),
+            StatusEffect::Slow(_) => Color::rgb(0.5, 0.8, 1.0),
+            StatusEffect::Stun => Color::rgb(1.0, 1.0, 0.3),
+            StatusEffect::Emp => Color::rgb(0.7, 0.4, 1.0),
+        }
+    }
+}
+
+/// Status effects handed out along with this entity's [`Damage`], and how many seconds each lasts
+#[derive(Component, Clone, Default, Debug)]
+struct Inflicts(Vec<(StatusEffect, f32)>);
+
+struct ActiveEffect {
+    effect: StatusEffect,
+    timer: Timer,
+    stacks: u32,
+}
+
+const MAX_BURN_STACKS: u32 = 5;
+
+/// Status effects currently on something. Only entities with this can be affected at all
+#[derive(Component, Default)]
+struct StatusEffects {
+    active: Vec<ActiveEffect>,
+    /// The sprite's color from before any tint was put on it
+    untinted: Option<Color>,
+    /// The tint last put on, so recoloring by anything else in the meantime can be noticed
+    tinted: Option<Color>,
+}
+impl StatusEffects {
+    fn apply(&mut self, effect: StatusEffect, duration: f32) {
+        let Some(existing) = self
+            .active
+            .iter_mut()
+            .find(|active| active.effect.same_kind(&effect))
+        else {
+            self.active.push(ActiveEffect {
+                effect,
+                timer: Timer::from_seconds(duration, TimerMode::Once),
+                stacks: 1,
+            });
+            return;
+        };
+        // Reapplying anything keeps whichever duration is longer
+        if existing.timer.remaining_secs() < duration {
+            existing.timer = Timer::from_seconds(duration, TimerMode::Once);
+        }
+        match (&mut existing.effect, effect) {
+            // Burns stack up in intensity
+            (StatusEffect::Burn(damage), StatusEffect::Burn(new_damage)) => {
+                *damage = damage.max(new_damage);
+                existing.stacks = (existing.stacks + 1).min(MAX_BURN_STACKS);
+            }
+            // Only the strongest slow counts
+            (StatusEffect::Slow(scale), StatusEffect::Slow(new_scale)) => {
+                *scale = scale.min(new_scale);
+            }
+            _ => {}
+        }
+    }
+
+    fn has(&self, effect: StatusEffect) -> bool {
+        self.active
+            .iter()
+            .any(|active| active.effect.same_kind(&effect))
+    }
+
+    fn speed_scale(&self) -> f32 {
+        self.active
+            .iter()
+            .map(|active| match active.effect {
+                StatusEffect::Slow(scale) => scale,
+                _ => 1.0,
+            })
+            .fold(1.0, f32::min)
+    }
+}
+
+fn tick_status_effects(
+    mut query: Query<(
+        Entity,
+        &mut StatusEffects,
+        Option<&mut Health>,
+        Option<&Resistances>,
+        Has<HitCounter>,
+    )>,
+    time: Res<Time>,
+    mut damage_dealt: EventWriter<DamageDealt>,
+) {
+    for (entity, mut status_effects, health, resistances, counts_hits) in query.iter_mut() {
+        for active in status_effects.active.iter_mut() {
+            active.timer.tick(time.delta());
+        }
+        status_effects
+            .active
+            .retain(|active| !active.timer.finished());
+
+        // Burning isn't a hit, so things that count hits don't take damage from it
+        let (Some(mut health), false) = (health, counts_hits) else {
+            continue;
+        };
+        for active in status_effects.active.iter() {
+            if let StatusEffect::Burn(damage_per_second) = active.effect {
+                let damage =
+                    Damage::Energy(damage_per_second * active.stacks as f32 * time.delta_seconds());
+                // Armor is per hit so it would soak up burning entirely.
+                // Burning is already under any shield so that gets skipped too
+                let amount = damage.against(resistances, None);
+                let was_alive = health.health > 0.0;
+                health.health -= amount;
+                damage_dealt.send(DamageDealt {
+                    target: entity,
+                    source: None,
+                    damage,
+                    amount,
+                    shielded: 0.0,
+                    fatal: was_alive && health.health <= 0.0,
+                });
+            }
+        }
+    }
+}
+
+/// Shows the most recent status effect on the sprite
+fn tint_status_effects(
+    mut query: Query<(
+        &mut StatusEffects,
+        Option<&mut Sprite>,
+        Option<&Handle<ColorMaterial>>,
+    )>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (mut status_effects, sprite, material) in query.iter_mut() {
+        // Meshes like asteroids keep their color in their own material
+        let current = match (
+            &sprite,
+            material.and_then(|material| materials.get(material)),
+        ) {
+            (Some(sprite), _) => sprite.color,
+            (None, Some(material)) => material.color,
+            (None, None) => continue,
+        };
+        // Something else like a boss changing phase recolored it under the tint,
+        // so that's the color to go back to. Fading only touches alpha so that's always kept
+        if let Some(tinted) = status_effects.tinted {
+            if tinted.with_a(current.a()) != current {
+                status_effects.untinted = Some(current);
+            }
+        }
+        let tint = status_effects
+            .active
+            .last()
+            .map(|active| active.effect.tint());
+        status_effects.tinted = tint;
+        let color = match tint {
+            Some(tint) => {
+                if status_effects.untinted.is_none() {
+                    status_effects.untinted = Some(current);
+                }
+                tint
+            }
+            None => match status_effects.untinted.take() {
+                Some(color) => color,
+                None => continue,
+            },
+        }
+        .with_a(current.a());
+        if current == color {
+            continue;
+        }
+        if let Some(mut sprite) = sprite {
+            sprite.color = color;
+        } else if let Some(material) = material.and_then(|material| materials.get_mut(material)) {
+            material.color = color;
+        }
+    }
+}
+
+#[derive(Component)]
+struct Lifetime {
+    time: Timer, // This could also be replaced with health and applying damage over time...
+    on_expire: Expire,
+}
+
+/// What happens when a [`Lifetime`] runs out
+#[derive(Clone, Debug)]
+enum Expire {
+    /// Nothing, for things that only use the timer like the tutorial fading
+    Nothing,
+    /// Dies like anything else, so on-death behaviors like [`ExplodesOnDeath`] run before it's despawned
+    Die,
+    /// Just removed, without any on-death behaviors
+    Despawn,
+    DespawnRecursive,
+    /// Fades out over this many seconds at the end of the lifetime, then is removed like [`Expire::DespawnRecursive`]
+    FadeOut(f32),
+    /// Replaces itself with a prefab
+    Spawn(Prefab),
+}
+
+/// Things that can be spawned from data alone, like when a [`Lifetime`] expires
+#[derive(Clone, Debug)]
+enum Prefab {
+    Asteroid {
+        size: f32,
+        material: AsteroidMaterial,
+    },
+    Pickup(Loot),
+}
+
+fn expire_lifetimes(
+    query: Query<(Entity, &Lifetime, Option<&Transform>), Without<Dead>>,
+    mut spawner: Spawner,
+    settings: Res<AsteroidSettings>,
+    mut rng: ResMut<GlobalEntropy<WyRand>>,
+    mut run: ResMut<Run>,
+    mut died: EventWriter<Died>,
+) {
+    for (entity, lifetime, transform) in query.iter() {
+        if !lifetime.time.just_finished() {
+            continue;
+        }
+        let position = transform.map_or(Vec3::ZERO, |transform| transform.translation);
+        match &lifetime.on_expire {
+            Expire::Nothing => continue,
+            Expire::Despawn => {
+                spawner.commands.entity(entity).despawn();
+                continue;
+            }
+            Expire::DespawnRecursive | Expire::FadeOut(_) => {
+                spawner.commands.entity(entity).despawn_recursive();
+                continue;
+            }
+            Expire::Die => {}
+            Expire::Spawn(Prefab::Asteroid { size, material }) => spawn_asteroid(
+                &mut spawner,
+                &settings,
+                &mut *rng,
+                position,
+                *size,
+                *material,
+                run.next_asteroid_seed(),
+            ),
+            Expire::Spawn(Prefab::Pickup(loot)) => spawn_pickup(
+                &mut spawner.commands,
+                &spawner.assets,
+                &mut *rng,
+                position,
+                *loot,
+            ),
+        }
+        spawner.commands.entity(entity).insert(Dead);
+        died.send(Died {
+            entity,
+            cause: DeathCause::Expired,
+        });
+    }
+}
+
+/// How opaque something was when it started fading out
+#[derive(Component)]
+struct FadeFrom(f32);
+
+fn fade_out_lifetimes(
+    mut query: Query<(
+        Entity,
+        &Lifetime,
+        Option<&FadeFrom>,
+        Option<&mut Sprite>,
+        Option<&Handle<ColorMaterial>>,
+    )>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut commands: Commands,
+) {
+    for (entity, lifetime, fade_from, sprite, material) in query.iter_mut() {
+        let Expire::FadeOut(duration) = lifetime.on_expire else {
+            continue;
+        };
+        let remaining = lifetime.time.remaining_secs();
+        if remaining > duration {
+            continue;
+        }
+        let material = material.and_then(|material| materials.get_mut(material));
+        let Some(fade_from) = fade_from else {
+            let alpha = match (&sprite, &material) {
+                (Some(sprite), _) => sprite.color.a(),
+                (None, Some(material)) => material.color.a(),
+                (None, None) => 1.0,
+            };
+            commands.entity(entity).insert(FadeFrom(alpha));
+            continue;
+        };
+        let alpha = fade_from.0 * remaining / duration.max(f32::EPSILON);
+        if let Some(mut sprite) = sprite {
+            sprite.color.set_a(alpha);
+        }
+        if let Some(material) = material {
+            material.color.set_a(alpha);
+        }
+    }
+}
+
+#[derive(Bundle)]
+struct ProjectileBundle {
+    affiliation: Affiliation,
+    sprite_bundle: SpriteBundle,
+    velocity: Velocity,
+    // TODO: Remove projectile maybe? I don't think I use it...
+    marker: Projectile, // TODO: Standardize calling this property marker or the type name
+    damage: Damage,
+    life: Lifetime,
+    collision: CollisionConfig,
+    mass: Mass,
+    already_hit: AlreadyHit,
+}
+
+fn tick_lifetime(time: Res<Time>, mut lifetimes: Query<&mut Lifetime>) {
+    for mut lifetime in lifetimes.iter_mut() {
+        lifetime.time.tick(time.delta());
+    }
+}
+
+#[derive(Component)]
+struct Bullet;
+// Running out of lifetime is handled by expire_lifetimes, this just uses bullets up when they hit something.
+// Pierce, Ricochet and SplitOnHit change what happens instead
+fn cull_bullets(
+    mut bullets: Query<
+        (
+            &mut Velocity,
+            &mut Transform,
+            &CollisionConfig,
+            Option<&Affiliation>,
+            Option<&Knockback>,
+            Option<&Handle<Image>>,
+            Option<&Sprite>,
+            Option<&mut AlreadyHit>,
+            Option<&mut Pierce>,
+            Option<&mut Ricochet>,
+            Option<&SplitOnHit>,
+        ),
+        With<Bullet>,
+    >,
+    asteroids: Query<(), With<Asteroid>>,
+    dead: Query<(), With<Dead>>,
+    mut collisions: EventReader<CollisionEvent>,
+    mut died: EventWriter<Died>,
+    mut commands: Commands,
+) {
+    // Dead only shows up once commands are applied, so bullets used up this frame are tracked here
+    let mut spent = Vec::new();
+    for collision in collisions.read() {
+        for i in 0..=1 {
+            let (entity, other) = (collision.entities[i], collision.entities[i.abs_diff(1)]);
+            if dead.contains(entity) || spent.contains(&entity) {
+                continue;
+            }
+            let Ok((
+                mut velocity,
+                mut transform,
+                config,
+                affiliation,
+                knockback,
+                texture,
+                sprite,
+                already_hit,
+                pierce,
+                ricochet,
+                split,
+            )) = bullets.get_mut(entity)
+            else {
+                continue;
+            };
+            if let Some(mut already_hit) = already_hit {
+                already_hit.0.push(other);
+            }
+            // Direction always points from the first entity to the second
+            let toward = collision.direction.extend(0.0) * if i == 0 { 1.0 } else { -1.0 };
+
+            // Bounces off asteroids keep going as if nothing happened
+            if let Some(mut ricochet) = ricochet.filter(|ricochet| ricochet.0 > 0) {
+                if asteroids.contains(other) {
+                    ricochet.0 -= 1;
+                    let speed_toward = velocity.translation_speed.dot(toward);
+                    if speed_toward > 0.0 {
+                        velocity.translation_speed -= 2.0 * speed_toward * toward;
+                    }
+                    if let Some(heading) = velocity.translation_speed.truncate().try_normalize() {
+                        transform.rotation = Quat::from_rotation_arc_2d(Vec2::Y, heading);
+                    }
+                    continue;
+                }
+            }
+
+            if let Some(split) = split {
+                let heading = velocity.translation_speed.truncate();
+                for k in 0..split.count {
+                    let angle = if split.count > 1 {
+                        split.spread * (k as f32 / (split.count - 1) as f32 - 0.5)
+                    } else {
+                        0.0
+                    };
+                    let heading = Vec2::from_angle(angle).rotate(heading);
+                    let mut child = commands.spawn((
+                        ProjectileBundle {
+                            affiliation: affiliation.copied().unwrap_or_default(),
+                            collision: CollisionConfig {
+                                radius: config.radius * SPLIT_SCALE,
+                                collision_resolution: CollisionResolutionStrat::Prevent,
+                                layers: config.layers,
+                            },
+                            damage: split.damage.clone(),
+                            life: Lifetime {
+                                time: Timer::from_seconds(split.lifetime, TimerMode::Once),
+                                on_expire: Expire::Despawn,
+                            },
+                            marker: Projectile,
+                            mass: Mass(1.0),
+                            velocity: Velocity {
+                                translation_speed: heading.extend(0.0),
+                                rotation_speed: 0.0,
+                            },
+                            sprite_bundle: SpriteBundle {
+                                sprite: sprite.cloned().unwrap_or_default(),
+                                transform: Transform {
+                                    translation: transform.translation,
+                                    rotation: heading
+                                        .try_normalize()
+                                        .map_or(transform.rotation, |heading| {
+                                            Quat::from_rotation_arc_2d(Vec2::Y, heading)
+                                        }),
+                                    scale: transform.scale * SPLIT_SCALE,
+                                },
+                                texture: texture.cloned().unwrap_or_default(),
+                                ..Default::default()
+                            },
+                            // Pieces start out already past whatever split them
+                            already_hit: AlreadyHit(vec![other]),
+                        },
+                        Bullet,
+                        Wrappable,
+                    ));
+                    if let Some(knockback) = knockback {
+                        child.insert(Knockback(knockback.0 * SPLIT_SCALE));
+                    }
+                }
+            }
+
+            if let Some(mut pierce) = pierce.filter(|pierce| pierce.0 > 0) {
+                pierce.0 -= 1;
+                continue;
+            }
+            spent.push(entity);
+            commands.entity(entity).insert(Dead);
+            died.send(Died {
+                entity,
+                cause: DeathCause::Spent,
+            });
+        }
+    }
+}
+
+/// Lets a projectile pass through this many more things before it's used up
+#[derive(Component, Clone, Copy, Debug)]
+struct Pierce(u32);
+
+/// Bounces off asteroids this many more times instead of being used up
+#[derive(Component, Clone, Copy, Debug)]
+struct Ricochet(u32);
+
+/// Breaks off into smaller projectiles fanned out across `spread` every time it hits something
+#[derive(Component, Clone, Debug)]
+struct SplitOnHit {
+    count: u32,
+    spread: f32,
+    /// Damage of each piece
+    damage: Damage,
+    /// Seconds each piece lasts
+    lifetime: f32,
+}
+
+/// How much smaller split pieces are than what they came from
+const SPLIT_SCALE: f32 = 0.6;
+
+/// Everything a projectile has already hit so passing through something only hits it once
+#[derive(Component, Default, Debug)]
+struct AlreadyHit(Vec<Entity>);
+
+#[derive(Event)]
+///  Represents the 2 entities involved in a collision
+struct CollisionEvent {
+    /// The root bodies that collided
+    entities: [Entity; 2],
+    /// The colliders that actually touched. Same as the entities unless they're part of a compound body
+    parts: [Entity; 2],
+    damage: [Option<Damage>; 2],
+    /// Status effects each entity hands out to the other along with its damage
+    inflicts: [Option<Inflicts>; 2],
+    /// Normalized  direction from entity 1 to 2
+    direction: Vec2,
+    knockback: [f32; 2],
+    /// Change in rotation speed for each entity from being struck off-center
+    spin: [f32; 2],
 }
 
-fn fps_text_update_system(
-    diagnostics: Res<DiagnosticsStore>,
-    mut query: Query<&mut Text, With<FpsText>>,
+#[derive(Default, PartialEq, Debug)]
+enum CollisionResolutionStrat {
+    Prevent,
+    NoYield,
+    #[default]
+    Yield,
+    /// Doesn't collide at all, just sends a [`SensorEvent`] for anything overlapping it
+    Sensor,
+}
+
+#[derive(Event)]
+/// Something overlapping a sensor. Sent every frame they overlap
+struct SensorEvent {
+    sensor: Entity,
+    other: Entity,
+}
+
+// Maybe this should be part of collision configs.
+#[derive(Component, Default, Debug)]
+struct Knockback(f32);
+
+/// How heavy something is. Together with the collision radius this gives the moment of inertia
+/// used when collisions impart spin.
+#[derive(Component, Debug)]
+struct Mass(f32);
+impl Default for Mass {
+    fn default() -> Self {
+        Mass(1.0)
+    }
+}
+impl Mass {
+    /// Everything is treated as a solid disc for now
+    fn moment_of_inertia(&self, radius: f32) -> f32 {
+        (0.5 * self.0 * radius.powi(2)).max(f32::EPSILON)
+    }
+}
+
+/// How much of the sliding speed at the contact point gets turned into spin each collision.
+/// 1.0 would stop the surfaces slipping entirely.
+const SPIN_FRICTION: f32 = 0.5;
+
+/// Works out the change in rotation speed for both bodies from the friction at the contact point.
+/// Bodies without a [`Mass`] neither give nor receive spin.
+fn contact_spin(
+    bodies: [(Vec2, f32, Option<&Velocity>, Option<&Mass>); 2],
+    contact: Vec2,
+    normal: Vec2,
+) -> [f32; 2] {
+    let (Some(mass1), Some(mass2)) = (bodies[0].3, bodies[1].3) else {
+        return [0.0; 2];
+    };
+    let offsets = [contact - bodies[0].0, contact - bodies[1].0];
+    let inertia = [
+        mass1.moment_of_inertia(bodies[0].1),
+        mass2.moment_of_inertia(bodies[1].1),
+    ];
+    // Velocity of each surface at the contact point, including its own spin
+    let surface_velocity = |velocity: Option<&Velocity>, offset: Vec2| {
+        velocity.map_or(Vec2::ZERO, |velocity| {
+            velocity.translation_speed.xy() + offset.perp() * velocity.rotation_speed
+        })
+    };
+    let tangent = normal.perp();
+    let slip = (surface_velocity(bodies[1].2, offsets[1])
+        - surface_velocity(bodies[0].2, offsets[0]))
+    .dot(tangent);
+    let resistance = 1.0 / mass1.0.max(f32::EPSILON)
+        + 1.0 / mass2.0.max(f32::EPSILON)
+        + offsets[0].perp_dot(tangent).powi(2) / inertia[0]
+        + offsets[1].perp_dot(tangent).powi(2) / inertia[1];
+    // Impulse along the tangent applied to entity 2, entity 1 gets the opposite
+    let impulse = tangent * -SPIN_FRICTION * slip / resistance;
+    [
+        offsets[0].perp_dot(-impulse) / inertia[0],
+        offsets[1].perp_dot(impulse) / inertia[1],
+    ]
+}
+
+/// The root body of `part` and where `part` is in the world right now. GlobalTransform isn't
+/// updated until after Update, so children are placed by working up through the local transforms instead
+fn locate(
+    part: Entity,
+    transforms: &Query<&Transform>,
+    parents: &Query<&Parent>,
+) -> Option<(Entity, Transform)> {
+    let mut body = part;
+    let mut offset = Transform::IDENTITY;
+    while let Ok(parent) = parents.get(body) {
+        offset = transforms.get(body).ok()?.mul_transform(offset);
+        body = parent.get();
+    }
+    Some((body, transforms.get(body).ok()?.mul_transform(offset)))
+}
+
+/// Lets systems that only read transforms find where colliders are this frame with [`locate`]
+#[derive(SystemParam)]
+struct Placements<'w, 's> {
+    transforms: Query<'w, 's, &'static Transform>,
+    parents: Query<'w, 's, &'static Parent>,
+}
+impl Placements<'_, '_> {
+    fn locate(&self, part: Entity) -> Option<(Entity, Transform)> {
+        locate(part, &self.transforms, &self.parents)
+    }
+}
+
+/// Everything [`check_collisions`] needs to know about one collider. Gathered up front so colliders
+/// on child entities can be checked and resolved as part of their root body.
+struct Collider<'a> {
+    /// The entity with the [`CollisionConfig`]
+    part: Entity,
+    /// The top of the part's hierarchy, which is what actually moves
+    body: Entity,
+    config: &'a CollisionConfig,
+    position: Vec2,
+    /// Corners of the [`ConvexHull`] in world space
+    hull: Option<Vec<Vec2>>,
+    affiliation: Option<Affiliation>,
+    damage: Option<&'a Damage>,
+    knockback: f32,
+    impact_damage: Option<&'a ImpactDamage>,
+    inflicts: Option<&'a Inflicts>,
+}
+
+/// Direction from the first collider to the second and how deep they overlap, if they do.
+/// Circles are checked directly and anything with a hull uses the separating axis test
+fn separation(collider1: &Collider, collider2: &Collider) -> Option<(Vec2, f32)> {
+    let offset = collider2.position - collider1.position;
+    if collider1.hull.is_none() && collider2.hull.is_none() {
+        let overlap = collider1.config.radius + collider2.config.radius - offset.length();
+        return (overlap > 0.0).then(|| (offset.normalize(), overlap));
+    }
+    let mut axes = Vec::new();
+    for (collider, other) in [(collider1, collider2), (collider2, collider1)] {
+        match (&collider.hull, &other.hull) {
+            (Some(hull), _) => axes.extend(
+                hull.iter()
+                    .zip(hull.iter().cycle().skip(1))
+                    .map(|(a, b)| (*b - *a).perp().normalize_or_zero()),
+            ),
+            // A circle can also be kept apart along the line to the nearest corner
+            (None, Some(hull)) => axes.extend(
+                hull.iter()
+                    .min_by(|a, b| {
+                        a.distance_squared(collider.position)
+                            .total_cmp(&b.distance_squared(collider.position))
+                    })
+                    .map(|corner| (*corner - collider.position).normalize_or_zero()),
+            ),
+            (None, None) => {}
+        }
+    }
+    let project = |collider: &Collider, axis: Vec2| match &collider.hull {
+        Some(hull) => hull
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(min, max), corner| {
+                (min.min(corner.dot(axis)), max.max(corner.dot(axis)))
+            }),
+        None => {
+            let center = collider.position.dot(axis);
+            (
+                center - collider.config.radius,
+                center + collider.config.radius,
+            )
+        }
+    };
+    let mut shallowest: Option<(Vec2, f32)> = None;
+    for axis in axes.into_iter().filter(|axis| *axis != Vec2::ZERO) {
+        let ((min1, max1), (min2, max2)) = (project(collider1, axis), project(collider2, axis));
+        let overlap = max1.min(max2) - min1.max(min2);
+        if overlap <= 0.0 {
+            return None;
+        }
+        if shallowest.map_or(true, |(_, shallowest)| overlap < shallowest) {
+            shallowest = Some((axis, overlap));
+        }
+    }
+    shallowest.map(|(axis, overlap)| (if axis.dot(offset) < 0.0 { -axis } else { axis }, overlap))
+}
+
+/// Everything [`check_collisions`] looks up about colliders and the bodies they belong to
+#[derive(SystemParam)]
+struct ColliderLookups<'w, 's> {
+    stats: Query<
+        'w,
+        's,
+        (
+            Option<&'static Affiliation>,
+            Option<&'static Damage>,
+            Option<&'static Knockback>,
+            Option<&'static ImpactDamage>,
+            Option<&'static Inflicts>,
+        ),
+    >,
+    motion: Query<'w, 's, (Option<&'static Velocity>, Option<&'static Mass>)>,
+    parents: Query<'w, 's, &'static Parent>,
+    already_hit: Query<'w, 's, &'static AlreadyHit>,
+}
+
+fn check_collisions(
+    mut events: EventWriter<CollisionEvent>,
+    mut sensor_events: EventWriter<SensorEvent>,
+    colliders: Query<(Entity, &CollisionConfig, Option<&ConvexHull>)>,
+    lookups: ColliderLookups,
+    mut transforms: Query<&mut Transform>,
+    relations: Res<FactionRelations>,
 ) {
-    for mut text in &mut query {
-        // try to get a "smoothed" FPS value from Bevy
-        if let Some(value) = diagnostics
-            .get(&FrameTimeDiagnosticsPlugin::FPS)
-            .and_then(|fps| fps.smoothed())
-        {
-            // Format the number as to leave space for 4 digits, just in case,
-            // right-aligned and rounded. This helps readability when the
-            // number changes rapidly.
-            text.sections[1].value = format!("{value:>4.0}");
+    let ColliderLookups {
+        stats,
+        motion,
+        parents,
+        already_hit,
+    } = lookups;
+    let has_hit = |a: Entity, b: Entity| {
+        already_hit
+            .get(a)
+            .map_or(false, |already_hit| already_hit.0.contains(&b))
+    };
+    let mut colliders: Vec<Collider> = colliders
+        .iter()
+        .filter_map(|(part, config, hull)| {
+            let (body, world) = locate(part, &transforms.to_readonly(), &parents)?;
+            let position = world.translation.xy();
+            let hull = hull.map(|hull| {
+                hull.0
+                    .iter()
+                    .map(|corner| world.transform_point(corner.extend(0.0)).xy())
+                    .collect()
+            });
+            // Parts use their own stats where they have them, otherwise their body's
+            let (affiliation, damage, knockback, impact_damage, inflicts) = stats.get(part).ok()?;
+            let body_stats = stats.get(body).ok()?;
+            Some(Collider {
+                part,
+                body,
+                config,
+                position,
+                hull,
+                affiliation: affiliation.or(body_stats.0).copied(),
+                damage: damage.or(body_stats.1),
+                knockback: knockback
+                    .or(body_stats.2)
+                    .map_or(0.0, |knockback| knockback.0),
+                impact_damage: impact_damage.or(body_stats.3),
+                // Only things that hit each target once hand out effects, otherwise
+                // staying in contact would stack them up every frame
+                inflicts: inflicts
+                    .or(body_stats.4)
+                    .filter(|_| already_hit.contains(body)),
+            })
+        })
+        .collect();
 
-            // Let's make it extra fancy by changing the color of the
-            // text according to the FPS value:
-            text.sections[1].style.color = if value >= 120.0 {
-                // Above 120 FPS, use green color
-                Color::rgb(0.0, 1.0, 0.0)
-            } else if value >= 60.0 {
-                // Between 60-120 FPS, gradually transition from yellow to green
-                Color::rgb((1.0 - (value - 60.0) / (120.0 - 60.0)) as f32, 1.0, 0.0)
-            } else if value >= 30.0 {
-                // Between 30-60 FPS, gradually transition from red to yellow
-                Color::rgb(1.0, ((value - 30.0) / (60.0 - 30.0)) as f32, 0.0)
+    // TODO: this might be easier if affiliations were their own components instead of an enum - past me
+    // not sure why... - later me
+    for i in 0..colliders.len() {
+        for j in i + 1..colliders.len() {
+            let (collider1, collider2) = (&colliders[i], &colliders[j]);
+            // Parts of the same body can't hit each other, and piercing things only hit once
+            if collider1.body == collider2.body
+                || has_hit(collider1.body, collider2.body)
+                || has_hit(collider2.body, collider1.body)
+                || !collider1
+                    .config
+                    .layers
+                    .interacts_with(&collider2.config.layers)
+            {
+                continue;
+            }
+            let relationship = relations.between(collider1.affiliation, collider2.affiliation);
+            let sensors = [
+                collider1.config.collision_resolution == CollisionResolutionStrat::Sensor,
+                collider2.config.collision_resolution == CollisionResolutionStrat::Sensor,
+            ];
+            // In the case that the entities would do nothing to each other, don't even check
+            if !relationship.notices()
+                || sensors[0] && sensors[1]
+                || !sensors[0] && !sensors[1] && !relationship.collides() && !relationship.damages()
+            {
+                continue;
+            }
+            let radius_sum = collider1.config.radius + collider2.config.radius;
+            if collider1.position.distance_squared(collider2.position) >= radius_sum.powi(2) {
+                continue;
+            }
+            let Some((direction, overlap)) = separation(collider1, collider2) else {
+                continue;
+            };
+            if sensors[0] || sensors[1] {
+                sensor_events.send(if sensors[0] {
+                    SensorEvent {
+                        sensor: collider1.part,
+                        other: collider2.body,
+                    }
+                } else {
+                    SensorEvent {
+                        sensor: collider2.part,
+                        other: collider1.body,
+                    }
+                });
+                continue;
+            }
+
+            // Collision detected
+            let mut difference = overlap * direction;
+            // Middle of the overlapping area
+            let contact =
+                collider1.position + direction * (collider1.config.radius - overlap / 2.0);
+            let motion = [
+                motion.get(collider1.body).unwrap_or((None, None)),
+                motion.get(collider2.body).unwrap_or((None, None)),
+            ];
+            let inflicts = if relationship.damages() {
+                [collider1.inflicts.cloned(), collider2.inflicts.cloned()]
             } else {
-                // Below 30 FPS, use red color
-                Color::rgb(1.0, 0.0, 0.0)
+                [None, None]
+            };
+            let damage = if !relationship.damages() {
+                // Non-hostile things only hurt each other by smashing together hard enough
+                let velocity_of = |velocity: Option<&Velocity>| {
+                    velocity.map_or(Vec2::ZERO, |velocity| velocity.translation_speed.xy())
+                };
+                let closing_speed =
+                    (velocity_of(motion[0].0) - velocity_of(motion[1].0)).dot(direction);
+                let impact = |impact_damage: Option<&ImpactDamage>| {
+                    impact_damage
+                        .map(|impact_damage| impact_damage.0 * closing_speed)
+                        .filter(|damage| *damage > 0.0)
+                        .map(Damage::Kinetic)
+                };
+                [
+                    impact(collider1.impact_damage),
+                    impact(collider2.impact_damage),
+                ]
+            } else {
+                [collider1.damage.cloned(), collider2.damage.cloned()]
+            };
+            // Compound bodies spin as if they were a disc reaching out to the part that was hit
+            let body_position = |collider: &Collider| {
+                transforms
+                    .get(collider.body)
+                    .map_or(collider.position, |transform| transform.translation.xy())
+            };
+            let body_positions = [body_position(collider1), body_position(collider2)];
+            events.send(CollisionEvent {
+                entities: [collider1.body, collider2.body],
+                parts: [collider1.part, collider2.part],
+                damage,
+                inflicts,
+                direction,
+                knockback: [collider1.knockback, collider2.knockback],
+                spin: contact_spin(
+                    [
+                        (
+                            body_positions[0],
+                            collider1
+                                .config
+                                .radius
+                                .max(body_positions[0].distance(contact)),
+                            motion[0].0,
+                            motion[0].1,
+                        ),
+                        (
+                            body_positions[1],
+                            collider2
+                                .config
+                                .radius
+                                .max(body_positions[1].distance(contact)),
+                            motion[1].0,
+                            motion[1].1,
+                        ),
+                    ],
+                    contact,
+                    direction,
+                ),
+            });
+
+            // Resolve the collision
+            if !relationship.collides()
+                || collider1.config.collision_resolution == CollisionResolutionStrat::Prevent
+                || collider2.config.collision_resolution == CollisionResolutionStrat::Prevent
+            {
+                continue;
+            }
+            let resolve_checks = [
+                collider1.config.collision_resolution == CollisionResolutionStrat::Yield,
+                collider2.config.collision_resolution == CollisionResolutionStrat::Yield,
+            ];
+            if resolve_checks[0] && resolve_checks[1] {
+                difference /= 2.0;
+            }
+            let bodies = [collider1.body, collider2.body];
+            for (body, resolve, shift) in [
+                (bodies[0], resolve_checks[0], -difference),
+                (bodies[1], resolve_checks[1], difference),
+            ] {
+                if !resolve {
+                    continue;
+                }
+                if let Ok(mut transform) = transforms.get_mut(body) {
+                    transform.translation += shift.extend(0.0);
+                }
+                // Keep every part of the body in step for the rest of the checks
+                for collider in colliders
+                    .iter_mut()
+                    .filter(|collider| collider.body == body)
+                {
+                    collider.position += shift;
+                    for corner in collider.hull.iter_mut().flatten() {
+                        *corner += shift;
+                    }
+                }
             }
-        } else {
-            // display "N/A" if we can't get a FPS measurement
-            // add an extra space to preserve alignment
-            text.sections[1].value = " N/A".into();
-            text.sections[1].style.color = Color::WHITE;
         }
     }
 }
 
-/// Toggle the FPS counter when pressing F12
-fn fps_counter_showhide(
-    mut q: Query<&mut Visibility, With<FpsRoot>>,
-    kbd: Res<ButtonInput<KeyCode>>,
+/// Spins things that were struck off-center
+fn apply_spin(mut query: Query<&mut Velocity>, mut collisions: EventReader<CollisionEvent>) {
+    for collision in collisions.read() {
+        for i in 0..=1 {
+            if let Ok(mut velocity) = query.get_mut(collision.entities[i]) {
+                velocity.rotation_speed += collision.spin[i];
+            }
+        }
+    }
+}
+
+fn draw_hitboxes(
+    mut gizmos: Gizmos,
+    query: Query<(&GlobalTransform, &CollisionConfig, Option<&ConvexHull>)>,
 ) {
-    if kbd.just_pressed(KeyCode::F12) {
-        let mut vis = q.single_mut();
-        *vis = match *vis {
-            Visibility::Hidden => Visibility::Visible,
-            _ => Visibility::Hidden,
+    for (transform, config, hull) in query.iter() {
+        let color = if config.collision_resolution == CollisionResolutionStrat::Sensor {
+            Color::GREEN
+        } else {
+            Color::BLUE
         };
+        match hull {
+            Some(hull) => gizmos.linestrip_2d(
+                hull.0
+                    .iter()
+                    .chain(hull.0.first())
+                    .map(|corner| transform.transform_point(corner.extend(0.0)).xy()),
+                color,
+            ),
+            None => {
+                gizmos.circle_2d(transform.translation().xy(), config.radius, color);
+            }
+        }
     }
 }
 
-#[derive(Component, PartialEq, Default, Debug)]
-enum Affiliation {
-    // should this just be part of collision config?
-    Friendly,
-    #[default]
-    Neutral,
-    Hostile,
+fn play_hit_sounds(
+    query: Query<(), Or<(With<Asteroid>, With<Enemy>)>>,
+    mut commands: Commands,
+    mut damage_dealt: EventReader<DamageDealt>,
+    assets: Res<AssetServer>,
+) {
+    for hit in damage_dealt.read() {
+        // Damage over time would spam this every frame
+        if hit.source.is_some() && query.contains(hit.target) {
+            commands.spawn(AudioBundle {
+                source: assets.load("hit1.wav"),
+                settings: PlaybackSettings::DESPAWN,
+            });
+        }
+    }
 }
 
-#[derive(Component)]
-struct Projectile;
-
-#[derive(Component, Clone, Debug)]
-enum Damage {
-    // should this just be part of collision config?
-    Basic(f32),
+fn knock_back_asteroids(
+    // Enemy ships get pushed around the same way
+    mut query: Query<&mut Velocity, Or<(With<Asteroid>, With<Enemy>)>>,
+    mut collisions: EventReader<CollisionEvent>,
+) {
+    // TODO rewrite cull_bullets in this way maybe. This is also kinda gross tho
+    for collision in collisions.read() {
+        for i in 0..=1 {
+            if let Ok(mut velocity) = query.get_mut(collision.entities[i]) {
+                velocity.translation_speed += collision.direction.extend(0.0)
+                    * collision.knockback[i.abs_diff(1)]
+                    // Negate if index 0
+                    * (1.0 + (-2.0 * i.abs_diff(1) as f32));
+            }
+        }
+    }
 }
-impl Default for Damage {
-    fn default() -> Self {
-        Damage::Basic(0.0)
+
+fn knock_back_player(
+    mut query: Query<&mut Velocity, With<Player>>,
+    mut collisions: EventReader<CollisionEvent>,
+) {
+    for collision in collisions.read() {
+        for i in 0..=1 {
+            if let Ok(mut velocity) = query.get_mut(collision.entities[i]) {
+                // Knockback, later considerations might include knockback resistance or inversion
+                velocity.translation_speed += collision.direction.extend(0.0)
+                    * collision.knockback[i.abs_diff(1)]
+                    // Negate if index 0
+                    * (1.0 + (-2.0 * i.abs_diff(1) as f32));
+
+                // Since there should only be one player, this skips checking the other
+                // collision entity if the first one is the player
+                break;
+            }
+        }
     }
 }
 
+#[derive(Event)]
+/// Something has died and will be despawned at the end of the frame.
+/// On-death behavior components react to this instead of each entity type despawning itself
+struct Died {
+    entity: Entity,
+    cause: DeathCause,
+}
+
+/// Already sent [`Died`], so nothing kills it again before it's despawned
 #[derive(Component)]
-struct Lifetime {
-    time: Timer, // This could also be replaced with health and applying damage over time...
+struct Dead;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum DeathCause {
+    /// Health ran out, from whatever dealt the last hit if anything
+    Damage { source: Option<Entity> },
+    /// Its [`Lifetime`] ran out
+    Expired,
+    /// Used itself up hitting something, like a bullet
+    Spent,
 }
 
-#[derive(Bundle)]
-struct ProjectileBundle {
-    affiliation: Affiliation,
-    sprite_bundle: SpriteBundle,
-    velocity: Velocity,
-    // TODO: Remove projectile maybe? I don't think I use it...
-    marker: Projectile, // TODO: Standardize calling this property marker or the type name
-    damage: Damage,
-    life: Lifetime,
-    collision: CollisionConfig,
+/// Anything whose health runs out dies
+fn kill_damaged(
+    mut damage_dealt: EventReader<DamageDealt>,
+    mut died: EventWriter<Died>,
+    dead: Query<(), With<Dead>>,
+    mut commands: Commands,
+) {
+    for hit in damage_dealt.read() {
+        if hit.fatal && !dead.contains(hit.target) {
+            commands.entity(hit.target).insert(Dead);
+            died.send(Died {
+                entity: hit.target,
+                cause: DeathCause::Damage { source: hit.source },
+            });
+        }
+    }
 }
 
-fn tick_lifetime(time: Res<Time>, mut lifetimes: Query<&mut Lifetime>) {
-    for mut lifetime in lifetimes.iter_mut() {
-        lifetime.time.tick(time.delta());
+fn despawn_dead(mut commands: Commands, mut died: EventReader<Died>) {
+    for death in died.read() {
+        commands.entity(death.entity).despawn_recursive();
     }
 }
 
-#[derive(Component)]
-struct Bullet;
-// Either have bullet and other timed types be in an enum and handle all of their deaths here in a big match
-// Or each projectile type has its own system to handle what happens when it dies
-// or whenever something dies to lifetime it just despawns and if i want things to happen on death
-// use health and constant damage over time... or doo something else special, maybe events can help
-// for now this will work
-// also, is there any situation where a lifetime ticking and checking to despawn need to be separate? or should all controllers/death handlers also do the ticking...?
-fn cull_bullets(
-    query: Query<(Entity, &Lifetime), With<Bullet>>,
-    mut commands: Commands,
-    mut collisions: EventReader<CollisionEvent>,
-) {
-    for (entity, lifetime) in query.iter() {
-        if lifetime.time.finished() {
-            commands.entity(entity).despawn();
+/// Breaks into smaller asteroids on death
+#[derive(Component, Debug)]
+struct Fragments {
+    max_pieces: i32,
+    min_pieces: i32,
+    /// Smallest a piece can be
+    piece_size: f32,
+}
+impl Default for Fragments {
+    fn default() -> Self {
+        Fragments {
+            max_pieces: 5,
+            min_pieces: 0,
+            piece_size: 10.0,
         }
     }
-    for collision in collisions.read() {
-        if query.contains(collision.entities[0]) {
-            commands.entity(collision.entities[0]).despawn();
+}
+
+fn break_asteroids(
+    query: Query<
+        (
+            &AsteroidShape,
+            &Transform,
+            &Velocity,
+            &Fragments,
+            &AsteroidMaterial,
+        ),
+        With<Asteroid>,
+    >,
+    mut died: EventReader<Died>,
+    mut rng: ResMut<GlobalEntropy<WyRand>>,
+    mut spawner: Spawner,
+    settings: Res<AsteroidSettings>,
+) {
+    for death in died.read() {
+        let Ok((shape, transform, velocity, fragments, material)) = query.get(death.entity) else {
+            continue;
+        };
+        let mut shape_rng = WyRand::seed_from_u64(shape.seed);
+        let size = (polygon_area_centroid(&shape.outline).0 / PI).sqrt() * 2.0;
+        let max_divisions = ((size / fragments.piece_size) as i32)
+            .min(fragments.max_pieces)
+            .max(1);
+        let divisions =
+            shape_rng.gen_range(fragments.min_pieces.min(max_divisions - 1)..max_divisions);
+        // Keep cutting the biggest piece along a random line so the pieces fit back together
+        let mut pieces = vec![shape.outline.clone()];
+        while pieces.len() < divisions.max(2) as usize {
+            let Some(biggest) = (0..pieces.len()).max_by(|a, b| {
+                polygon_area_centroid(&pieces[*a])
+                    .0
+                    .total_cmp(&polygon_area_centroid(&pieces[*b]).0)
+            }) else {
+                break;
+            };
+            let piece = pieces.swap_remove(biggest);
+            let centroid = polygon_area_centroid(&piece).1;
+            let cut = slice_polygon(
+                &piece,
+                centroid,
+                Vec2::from_angle(shape_rng.gen_range(0.0..PI)),
+            );
+            if cut.len() < 2 || cut.iter().any(|piece| piece.len() < 3) {
+                pieces.push(piece);
+                break;
+            }
+            pieces.extend(cut);
         }
-        if query.contains(collision.entities[1]) {
-            commands.entity(collision.entities[1]).despawn();
+        // Cuts can make more pieces than needed, and with a single division only one half survives.
+        // The biggest are the ones kept
+        pieces.sort_by(|a, b| {
+            polygon_area_centroid(b)
+                .0
+                .total_cmp(&polygon_area_centroid(a).0)
+        });
+        for piece in pieces.into_iter().take(divisions as usize) {
+            let centroid = polygon_area_centroid(&piece).1;
+            let offset = transform.rotation * centroid.extend(0.0);
+            // Pieces carry on with the parent and drift apart from where they were cut
+            let push = offset.normalize_or_zero() * rng.gen_range(20.0..60.0);
+            spawn_asteroid_shape(
+                &mut spawner,
+                &settings,
+                Transform {
+                    translation: transform.translation + offset,
+                    ..*transform
+                },
+                AsteroidShape {
+                    outline: piece.iter().map(|point| *point - centroid).collect(),
+                    // Pieces break up the same way every time too
+                    seed: shape_rng.gen(),
+                },
+                *material,
+                Velocity {
+                    translation_speed: velocity.translation_speed + push,
+                    rotation_speed: velocity.rotation_speed + rng.gen_range(-0.5..0.5),
+                },
+            );
         }
     }
 }
 
-#[derive(Event)]
-///  Represents the 2 entities involved in a collision
-struct CollisionEvent {
-    entities: [Entity; 2],
-    damage: [Option<Damage>; 2],
-    /// Normalized  direction from entity 1 to 2
-    direction: Vec2,
-    knockback: [f32; 2],
+/// Sets off an [`Explosion`] on death
+#[derive(Component, Clone, Debug)]
+struct ExplodesOnDeath(Explosion);
+
+/// A blast that hurts and pushes away everything within its radius the moment it appears
+#[derive(Component, Clone, Default, Debug)]
+struct Explosion {
+    radius: f32,
+    damage: Damage,
+    knockback: f32,
+    inflicts: Inflicts,
+}
+
+fn spawn_explosion(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    position: Vec3,
+    explosion: Explosion,
+    affiliation: Option<Affiliation>,
+    instigator: Option<Affiliation>,
+) {
+    let mut entity = commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(Circle::new(explosion.radius))),
+            material: materials.add(Color::rgba(1.0, 0.6, 0.2, 0.6)),
+            // Draw over whatever it hit
+            transform: Transform::from_translation(position.truncate().extend(10.0)),
+            ..Default::default()
+        },
+        Lifetime {
+            time: Timer::from_seconds(0.3, TimerMode::Once),
+            on_expire: Expire::FadeOut(0.3),
+        },
+        explosion,
+    ));
+    // Takes the side of whatever blew up so it follows the same faction rules
+    if let Some(affiliation) = affiliation {
+        entity.insert(affiliation);
+    }
+    if let Some(instigator) = instigator {
+        entity.insert(Instigator(instigator));
+    }
+}
+
+/// The side that set off an explosion, credited with whatever it kills.
+/// Passed along to anything the explosion sets off so whole chains count for whoever started them
+#[derive(Component, Clone, Copy, Debug)]
+struct Instigator(Affiliation);
+
+/// Which side gets credit for what `source` does
+fn credited_side(
+    sides: &Query<(Option<&Instigator>, Option<&Affiliation>)>,
+    source: Entity,
+) -> Option<Affiliation> {
+    let (instigator, affiliation) = sides.get(source).ok()?;
+    instigator
+        .map(|instigator| instigator.0)
+        .or(affiliation.copied())
 }
 
-#[derive(Default, PartialEq, Debug)]
-enum CollisionResolutionStrat {
-    Prevent,
-    NoYield,
-    #[default]
-    Yield,
+fn explode_on_death(
+    query: Query<(&Transform, &ExplodesOnDeath, Option<&Affiliation>)>,
+    sides: Query<(Option<&Instigator>, Option<&Affiliation>)>,
+    mut commands: Commands,
+    mut died: EventReader<Died>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for death in died.read() {
+        if let Ok((transform, explodes, affiliation)) = query.get(death.entity) {
+            let instigator = match death.cause {
+                DeathCause::Damage {
+                    source: Some(source),
+                } => credited_side(&sides, source),
+                // Nothing else was involved so it set itself off
+                _ => credited_side(&sides, death.entity),
+            };
+            spawn_explosion(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                transform.translation,
+                explodes.0.clone(),
+                affiliation.copied(),
+                instigator,
+            );
+        }
+    }
 }
 
-// Maybe this should be part of collision configs.
-#[derive(Component, Default, Debug)]
-struct Knockback(f32);
+/// Fraction of full damage and knockback at the very edge of an explosion
+const EXPLOSION_EDGE_FALLOFF: f32 = 0.2;
+/// Seconds between an explosion catching something explosive and that going off too
+const CHAIN_DELAY: f32 = 0.15;
 
-fn check_collisions(
-    mut events: EventWriter<CollisionEvent>,
-    mut query: Query<(
+/// Explosions hit through the normal collision pipeline so damage, knockback and status effects all apply.
+/// Anything explosive they catch gets set off a moment later
+fn detonate_explosions(
+    explosions: Query<
+        (
+            Entity,
+            &Transform,
+            &Explosion,
+            Option<&Affiliation>,
+            Option<&Instigator>,
+        ),
+        Added<Explosion>,
+    >,
+    colliders: Query<(
         Entity,
         &CollisionConfig,
-        &mut Transform,
-        Option<&Affiliation>,
-        Option<&Damage>,
-        Option<&Knockback>,
+        Has<ExplodesOnDeath>,
+        Has<Detonating>,
     )>,
+    affiliations: Query<&Affiliation>,
+    // So parts of bodies are hit where they actually are, even if they were only just spawned
+    placements: Placements,
+    relations: Res<FactionRelations>,
+    mut collisions: EventWriter<CollisionEvent>,
+    mut commands: Commands,
 ) {
-    // TODO: this might be easier if affiliations were their own components instead of an enum - past me
-    // not sure why... - later me
-    // This needs to be a let and while instead of a for loop or compiler gets sad???
-    let mut iter = query.iter_combinations_mut();
-    while let Some([mut entity1, mut entity2]) = iter.fetch_next() {
-        // TODO Make this more readable
-        // In the case that the entities are of the same affiliation, don't even check
-        if entity1.3.is_some() && entity1.3 == entity2.3 {
-            continue;
-        }
-        let radius_sum = entity1.1.radius + entity2.1.radius;
-        if entity1
-            .2
-            .translation
-            .xy()
-            .distance_squared(entity2.2.translation.xy())
-            < (radius_sum).powi(2)
-        {
-            let direction = (entity2.2.translation.xy() - entity1.2.translation.xy()).normalize();
-            let mut difference = (radius_sum
-                - entity1
-                    .2
-                    .translation
-                    .xy()
-                    .distance(entity2.2.translation.xy()))
-                * direction;
-            // Collision detected
-            events.send(CollisionEvent {
-                entities: [entity1.0, entity2.0],
-                damage: [entity1.4.cloned(), entity2.4.cloned()],
-                direction: direction,
-                knockback: [
-                    entity1.5.unwrap_or(&Knockback(0.0)).0,
-                    entity2.5.unwrap_or(&Knockback(0.0)).0,
-                ],
-            });
-            // Resolve the collision
-            if entity1.1.collision_resolution == CollisionResolutionStrat::Prevent
-                || entity2.1.collision_resolution == CollisionResolutionStrat::Prevent
-            {
+    for (explosion_entity, explosion_transform, explosion, explosion_affiliation, instigator) in
+        explosions.iter()
+    {
+        // Whoever set this one off decides who it hurts, and gets credit for anything it sets off too
+        let instigator = instigator
+            .map(|instigator| instigator.0)
+            .or(explosion_affiliation.copied());
+        let mut detonated = Vec::new();
+        for (part, config, explosive, detonating) in colliders.iter() {
+            let Some((body, part_transform)) = placements.locate(part) else {
+                continue;
+            };
+            let offset = part_transform.translation.xy() - explosion_transform.translation.xy();
+            // Measured to the edge so big things get caught by their edge
+            let reach = (offset.length() - config.radius).max(0.0);
+            if reach > explosion.radius {
                 continue;
             }
-            let resolve_checks = [
-                entity1.1.collision_resolution == CollisionResolutionStrat::Yield,
-                entity2.1.collision_resolution == CollisionResolutionStrat::Yield,
-            ];
-            if resolve_checks[0] && resolve_checks[1] {
-                difference /= 2.0;
+            let part_affiliation = affiliations
+                .get(part)
+                .or_else(|_| affiliations.get(body))
+                .ok();
+            let relationship = relations.between(instigator, part_affiliation.copied());
+            if !relationship.notices() {
+                continue;
             }
-            if resolve_checks[0] {
-                entity1.2.translation -= difference.extend(0.0);
+            if explosive && !detonating && !detonated.contains(&body) {
+                detonated.push(body);
+                // Missiles and mines can be despawned by their own expiry this frame
+                let mut detonation = commands.entity(body);
+                detonation.try_insert(Detonating(Timer::from_seconds(
+                    CHAIN_DELAY,
+                    TimerMode::Once,
+                )));
+                if let Some(instigator) = instigator {
+                    detonation.try_insert(Instigator(instigator));
+                }
             }
-            if resolve_checks[1] {
-                entity2.2.translation += difference.extend(0.0);
+            // Sensors and projectiles only ever get set off, never pushed around or hurt
+            if config.collision_resolution == CollisionResolutionStrat::Sensor
+                || config.layers.membership & CollisionLayers::PROJECTILE != 0
+                || !relationship.damages() && !relationship.collides()
+            {
+                continue;
             }
+            let falloff =
+                1.0 - (1.0 - EXPLOSION_EDGE_FALLOFF) * reach / explosion.radius.max(f32::EPSILON);
+            collisions.send(CollisionEvent {
+                entities: [explosion_entity, body],
+                parts: [explosion_entity, part],
+                damage: [
+                    relationship
+                        .damages()
+                        .then(|| explosion.damage.scaled(falloff)),
+                    None,
+                ],
+                inflicts: [
+                    relationship.damages().then(|| explosion.inflicts.clone()),
+                    None,
+                ],
+                direction: offset.normalize_or_zero(),
+                knockback: [explosion.knockback * falloff, 0.0],
+                spin: [0.0; 2],
+            });
         }
     }
 }
 
-fn draw_hitboxes(mut gizmos: Gizmos, query: Query<(&Transform, &CollisionConfig)>) {
-    for hitbox in query.iter() {
-        gizmos.circle_2d(hitbox.0.translation.xy(), hitbox.1.radius, Color::BLUE);
+/// Caught in an explosion and about to go off itself. Its [`Instigator`] is credited with the kill
+#[derive(Component)]
+struct Detonating(Timer);
+
+fn tick_detonations(
+    mut query: Query<(Entity, &mut Detonating), Without<Dead>>,
+    time: Res<Time>,
+    mut died: EventWriter<Died>,
+    mut commands: Commands,
+) {
+    for (entity, mut detonating) in query.iter_mut() {
+        if detonating.0.tick(time.delta()).just_finished() {
+            commands.entity(entity).remove::<Detonating>().insert(Dead);
+            // The explosion that caught it may be gone by now, so it carries its own credit
+            died.send(Died {
+                entity,
+                cause: DeathCause::Damage {
+                    source: Some(entity),
+                },
+            });
+        }
     }
 }
 
-fn break_asteroids(
-    mut query: Query<(Entity, &mut Health, &Transform, &mut Velocity), With<Asteroid>>,
+#[derive(Clone, Copy, Debug)]
+enum Loot {
+    Currency(u32),
+    /// Health given back
+    Repair(f32),
+    /// Added to the heat capacity of every weapon
+    HeatCapacity(f32),
+    /// Added to how fast every weapon cools
+    Cooling(f32),
+    /// Extra things every weapon's projectiles pass through
+    Pierce(u32),
+    /// Extra bounces off asteroids for every weapon's projectiles
+    Ricochet(u32),
+    /// Extra pieces every weapon's projectiles break into when they hit
+    Split(u32),
+}
+
+/// What might be dropped on death, each with its chance out of 1
+#[derive(Component, Clone, Default, Debug)]
+struct DropsLoot(Vec<(Loot, f32)>);
+
+/// Loot floating around waiting for the player to fly into it
+#[derive(Component)]
+struct Pickup(Loot);
+
+fn spawn_pickup(
+    commands: &mut Commands,
+    assets: &AssetServer,
+    rng: &mut impl Rng,
+    position: Vec3,
+    loot: Loot,
+) {
+    let direction = rng.gen_range(0.0..PI * 2.0);
+    commands.spawn((
+        Pickup(loot),
+        SpriteBundle {
+            // TODO: Proper pickup sprites
+            texture: assets.load("basic_bullet_50.png"),
+            transform: Transform::from_translation(position),
+            sprite: Sprite {
+                color: match loot {
+                    Loot::Currency(_) => Color::GOLD,
+                    Loot::Repair(_) => Color::GREEN,
+                    Loot::HeatCapacity(_) => Color::ORANGE_RED,
+                    Loot::Cooling(_) => Color::CYAN,
+                    Loot::Pierce(_) => Color::WHITE,
+                    Loot::Ricochet(_) => Color::SILVER,
+                    Loot::Split(_) => Color::FUCHSIA,
+                },
+                custom_size: Some(Vec2::splat(25.0)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        CollisionConfig {
+            radius: 20.0,
+            collision_resolution: CollisionResolutionStrat::Sensor,
+            layers: CollisionLayers {
+                membership: CollisionLayers::PICKUP,
+                filter: CollisionLayers::SHIP,
+            },
+        },
+        Velocity {
+            translation_speed: Vec3::new(direction.cos(), direction.sin(), 0.0) * 30.0,
+            rotation_speed: 1.0,
+        },
+        Wrappable,
+        Lifetime {
+            time: Timer::from_seconds(15.0, TimerMode::Once),
+            on_expire: Expire::FadeOut(3.0),
+        },
+    ));
+}
+
+fn drop_loot(
+    query: Query<(&Transform, &DropsLoot)>,
     mut commands: Commands,
-    mut collisions: EventReader<CollisionEvent>,
+    mut died: EventReader<Died>,
     mut rng: ResMut<GlobalEntropy<WyRand>>,
     assets: Res<AssetServer>,
 ) {
-    // TODO rewrite cull_bullets in this way maybe. This is also kinda gross tho
-    for collision in collisions.read() {
-        for i in 0..=1 {
-            if let Ok((entity, mut health, transform, mut velocity)) =
-                query.get_mut(collision.entities[i])
-            {
-                // Asteroid collision
-
-                if let Some(damage) = &collision.damage[i.abs_diff(1)] {
-                    match damage {
-                        Damage::Basic(dmg) => {
-                            health.health -= dmg;
-                        }
-                    }
-                    commands.spawn(AudioBundle {
-                        source: assets.load("hit1.wav"),
-                        settings: PlaybackSettings::DESPAWN,
-                    });
+    for death in died.read() {
+        let Ok((transform, drops)) = query.get(death.entity) else {
+            continue;
+        };
+        for (loot, chance) in drops.0.iter() {
+            if rng.gen_range(0.0..1.0) >= *chance {
+                continue;
+            }
+            spawn_pickup(
+                &mut commands,
+                &assets,
+                &mut *rng,
+                transform.translation,
+                *loot,
+            );
+        }
+    }
+}
 
-                    // Only need to check if the asteroid should die if its health changed,
-                    // which is presumed to only happen here
-                    if health.health <= 0.0 {
-                        commands.entity(entity).despawn();
-                        commands.spawn(AudioBundle {
-                            source: assets.load("hit2.wav"),
-                            settings: PlaybackSettings::DESPAWN,
-                        });
-                        // Fragment
-                        let size = health.max * 2.0;
-                        let max_divisions = (size / 10.0).min(5.0) as i32;
-                        let divisions = rng.gen_range(0..max_divisions);
-                        let new_size = size / divisions.max(2) as f32;
-                        // TODO make this more random and conserve momentum
-                        for _d in 0..divisions {
-                            let direction = rng.gen_range(0.0..PI * 2.0);
-                            let speed = rng.gen_range(0.0..3000.0 / new_size);
-                            commands.spawn(AsteroidBundle {
-                                collision: CollisionConfig {
-                                    radius: new_size / 2.0,
-                                    ..Default::default()
-                                },
-                                damage: Damage::Basic(new_size / 3.0),
-                                health: Health {
-                                    health: new_size / 2.0,
-                                    max: new_size / 2.0,
-                                },
-                                sprite_bundle: SpriteBundle {
-                                    sprite: Sprite {
-                                        custom_size: Some(Vec2::splat(new_size)),
-                                        ..Default::default()
-                                    },
-                                    transform: Transform::from_translation(transform.translation),
-                                    texture: assets.load("basic_asteroid_100.png"),
-                                    ..Default::default()
-                                },
-                                velocity: Velocity {
-                                    translation_speed: Vec3 {
-                                        x: direction.cos(),
-                                        y: direction.sin(),
-                                        z: 0.0,
-                                    } * speed,
-                                    rotation_speed: rng
-                                        .gen_range(-100.0 / new_size..100.0 / new_size),
-                                },
-                                knockback: Knockback(size * 2.0),
-                                ..Default::default()
-                            });
+fn collect_pickups(
+    pickups: Query<&Pickup, Without<Dead>>,
+    mut players: Query<(&mut Health, Option<&mut Weapon>, Option<&mut WeaponRack>), With<Player>>,
+    mut sensor_events: EventReader<SensorEvent>,
+    mut died: EventWriter<Died>,
+    mut commands: Commands,
+    mut run: ResMut<Run>,
+) {
+    // Dead only shows up once commands are applied, so pickups collected this frame are tracked here
+    let mut collected = Vec::new();
+    for sensed in sensor_events.read() {
+        let (Ok(pickup), Ok((mut health, weapon, rack))) =
+            (pickups.get(sensed.sensor), players.get_mut(sensed.other))
+        else {
+            continue;
+        };
+        if collected.contains(&sensed.sensor) {
+            continue;
+        }
+        collected.push(sensed.sensor);
+        // Upgrades go to the mounted weapon and everything in the rack
+        let mut weapons: Vec<&mut Weapon> = rack
+            .map(|rack| rack.into_inner().weapons.iter_mut().collect())
+            .unwrap_or_default();
+        weapons.extend(weapon.map(|weapon| weapon.into_inner()));
+        match pickup.0 {
+            Loot::Currency(amount) => run.currency += amount,
+            Loot::Repair(amount) => health.health = (health.health + amount).min(health.max),
+            Loot::HeatCapacity(amount) => weapons
+                .iter_mut()
+                .for_each(|weapon| weapon.heat.capacity += amount),
+            Loot::Cooling(amount) => weapons
+                .iter_mut()
+                .for_each(|weapon| weapon.heat.cooling += amount),
+            Loot::Pierce(amount) => weapons
+                .iter_mut()
+                .for_each(|weapon| weapon.pierce += amount),
+            Loot::Ricochet(amount) => weapons
+                .iter_mut()
+                .for_each(|weapon| weapon.ricochet += amount),
+            Loot::Split(amount) => {
+                for weapon in weapons.iter_mut() {
+                    match &mut weapon.split {
+                        Some(split) => split.count += amount,
+                        None => {
+                            weapon.split = Some(SplitOnHit {
+                                count: amount,
+                                spread: 0.8,
+                                damage: weapon.damage.scaled(0.3),
+                                lifetime: 0.4,
+                            })
                         }
                     }
                 }
-                // Knockback
-                velocity.translation_speed += collision.direction.extend(0.0)
-                    * collision.knockback[i.abs_diff(1)]
-                    // Negate if index 0
-                    * (1.0 + (-2.0 * i.abs_diff(1) as f32));
             }
         }
+        commands.entity(sensed.sensor).insert(Dead);
+        died.send(Died {
+            entity: sensed.sensor,
+            cause: DeathCause::Spent,
+        });
     }
 }
 
-fn hurt_player(
-    mut query: Query<(Entity, &mut Health, &mut Velocity), With<Player>>,
+/// Played on death
+#[derive(Component, Default, Debug)]
+struct DeathSound(Handle<AudioSource>);
+
+fn play_death_sounds(
+    query: Query<&DeathSound>,
     mut commands: Commands,
-    mut collisions: EventReader<CollisionEvent>,
+    mut died: EventReader<Died>,
 ) {
-    for collision in collisions.read() {
-        for i in 0..=1 {
-            if let Ok((entity, mut health, mut velocity)) = query.get_mut(collision.entities[i]) {
-                // Player collision
-                if let Some(damage) = &collision.damage[i.abs_diff(1)] {
-                    match damage {
-                        Damage::Basic(dmg) => {
-                            health.health -= dmg;
-                        }
-                    }
+    for death in died.read() {
+        if let Ok(sound) = query.get(death.entity) {
+            commands.spawn(AudioBundle {
+                source: sound.0.clone(),
+                settings: PlaybackSettings::DESPAWN,
+            });
+        }
+    }
+}
 
-                    // Only need to check if the player should die if its health changed,
-                    // which is presumed to only happen here.
-                    if health.health <= 0.0 {
-                        // TODO game over. for now this will prob crash on player death
-                        commands.entity(entity).despawn();
-                    }
-                }
-                // Knockback, later considerations might include knockback resistance or inversion
-                velocity.translation_speed += collision.direction.extend(0.0)
-                    * collision.knockback[i.abs_diff(1)]
-                    // Negate if index 0
-                    * (1.0 + (-2.0 * i.abs_diff(1) as f32));
+/// Score for the current run
+#[derive(Resource)]
+struct Run {
+    /// Everything random in the run comes from this
+    seed: u64,
+    /// Counts up so each asteroid gets its own seed
+    asteroids_spawned: u64,
+    score: u32,
+    currency: u32,
+    /// Ships left, including the one flying right now
+    lives: u32,
+    /// Score at which the next extra life is given
+    next_extra_life: u32,
+}
 
-                // Since there should only be one player, this skips checking the other
-                // collision entity if the first one is the player
-                break;
-            }
+const STARTING_LIVES: u32 = 3;
+/// An extra life is given every time score passes another multiple of this
+const EXTRA_LIFE_SCORE: u32 = 1000;
+/// Seconds between dying and the ship coming back
+const RESPAWN_DELAY: f32 = 2.0;
+/// Seconds of invulnerability after respawning
+const RESPAWN_PROTECTION: f32 = 3.0;
+
+impl Run {
+    fn next_asteroid_seed(&mut self) -> u64 {
+        self.asteroids_spawned += 1;
+        // Spread consecutive counts out so neighbouring asteroids don't get similar seeds
+        self.seed ^ self.asteroids_spawned.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+    }
+}
+impl Default for Run {
+    fn default() -> Self {
+        Run {
+            seed: 0,
+            asteroids_spawned: 0,
+            score: 0,
+            currency: 0,
+            lives: STARTING_LIVES,
+            next_extra_life: EXTRA_LIFE_SCORE,
+        }
+    }
+}
+
+/// Score given for killing this
+#[derive(Component, Default, Debug)]
+struct ScoreValue(u32);
+
+fn award_score(
+    query: Query<&ScoreValue>,
+    sides: Query<(Option<&Instigator>, Option<&Affiliation>)>,
+    mut died: EventReader<Died>,
+    mut run: ResMut<Run>,
+) {
+    for death in died.read() {
+        let (Ok(score), DeathCause::Damage { source }) = (query.get(death.entity), death.cause)
+        else {
+            continue;
+        };
+        // Only count kills by the player's side. Sourceless damage like burning probably came from them too
+        if source.map_or(true, |source| {
+            credited_side(&sides, source) == Some(Affiliation::Friendly)
+        }) {
+            run.score += score.0;
         }
     }
 }
 
+fn award_extra_lives(mut run: ResMut<Run>) {
+    while run.score >= run.next_extra_life {
+        run.lives += 1;
+        run.next_extra_life += EXTRA_LIFE_SCORE;
+    }
+}
+
+/// Counting down to the player coming back after dying
+#[derive(Resource)]
+struct Respawn(Timer);
+
 #[derive(Component)]
-struct UiHealthBack;
+struct GameOverText;
+
+fn lose_life(
+    players: Query<(), With<Player>>,
+    main_camera: Query<Entity, With<MainCamera>>,
+    mut died: EventReader<Died>,
+    mut run: ResMut<Run>,
+    mut commands: Commands,
+) {
+    for death in died.read() {
+        if !players.contains(death.entity) {
+            continue;
+        }
+        // The ship that just died was one of the lives
+        run.lives = run.lives.saturating_sub(1);
+        if run.lives > 0 {
+            commands.insert_resource(Respawn(Timer::from_seconds(RESPAWN_DELAY, TimerMode::Once)));
+        } else {
+            // TODO: Proper game over screen with restart
+            commands.spawn((
+                TargetCamera(main_camera.single()),
+                GameOverText,
+                TextBundle {
+                    text: Text::from_section(
+                        format!("GAME OVER\nSeed {}", run.seed),
+                        TextStyle {
+                            font_size: 64.0,
+                            color: Color::WHITE,
+                            ..Default::default()
+                        },
+                    ),
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        align_self: AlignSelf::Center,
+                        justify_self: JustifySelf::Center,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            ));
+        }
+    }
+}
+
+fn respawn_player(
+    respawn: Option<ResMut<Respawn>>,
+    time: Res<Time>,
+    hazards: Query<(&Transform, &CollisionConfig), (With<Health>, Without<Parent>)>,
+    background: Query<&Background>,
+    mut rng: ResMut<GlobalEntropy<WyRand>>,
+    mut spawner: Spawner,
+) {
+    let Some(mut respawn) = respawn else {
+        return;
+    };
+    if !respawn.0.tick(time.delta()).finished() {
+        return;
+    }
+    spawner.commands.remove_resource::<Respawn>();
+
+    // Try a handful of random spots and take whichever has the most room around it
+    let world_size = background.single().size;
+    let half_size = world_size / 2.0;
+    let position = (0..20)
+        .map(|_| {
+            Vec2::new(
+                rng.gen_range(-half_size.x..half_size.x),
+                rng.gen_range(-half_size.y..half_size.y),
+            )
+        })
+        .map(|candidate| {
+            let clearance = hazards
+                .iter()
+                .map(|(transform, collision)| {
+                    // Hazards just across the edge of the world are close too
+                    wrapped_offset(candidate, transform.translation.truncate(), world_size).length()
+                        - collision.radius
+                })
+                .fold(f32::INFINITY, f32::min);
+            (candidate, clearance)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(Vec2::ZERO, |(candidate, _)| candidate);
+
+    let player = spawn_player(
+        &mut spawner.commands,
+        &spawner.assets,
+        &mut spawner.meshes,
+        &mut spawner.materials,
+        position.extend(0.0),
+    );
+    spawner
+        .commands
+        .entity(player)
+        .insert(Invulnerable::new(RESPAWN_PROTECTION));
+}
+
 #[derive(Component)]
-struct UiHealthFront;
+struct UiLives;
+
+fn update_lives_ui(mut text: Query<&mut Text, With<UiLives>>, run: Res<Run>) {
+    for mut text in text.iter_mut() {
+        text.sections[0].value = format!("Lives: {}", run.lives);
+    }
+}
+
+/// A bar on the HUD. The back shows the max of a stat and the front the current value
+#[derive(Component, Clone, Copy)]
+enum UiBar {
+    HealthBack,
+    HealthFront,
+    ShieldBack,
+    ShieldFront,
+    ChargeBack,
+    ChargeFront,
+    HeatBack,
+    HeatFront,
+    BossBack,
+    BossFront,
+}
+
+fn spawn_ui_bar(parent: &mut ChildBuilder, back: UiBar, front: UiBar, color: Color) {
+    parent
+        .spawn((
+            NodeBundle {
+                background_color: BackgroundColor(Color::Rgba {
+                    red: 0.3,
+                    green: 0.3,
+                    blue: 0.3,
+                    alpha: 1.0,
+                }),
+                style: Style {
+                    height: Val::Px(15.0),
+                    width: Val::Px(100.0),
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            back,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    background_color: BackgroundColor(color),
+                    style: Style {
+                        width: Val::Percent(50.0),
+                        height: Val::Percent(100.0),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                front,
+            ));
+        });
+}
 
 fn setup_ui(mut commands: Commands, main_camera: Query<Entity, With<MainCamera>>) {
     // TODO: move fps to this root since i think you can only have one root. Maybe????
@@ -969,10 +5215,11 @@ fn setup_ui(mut commands: Commands, main_camera: Query<Entity, With<MainCamera>>
                 style: Style {
                     position_type: PositionType::Absolute,
                     bottom: Val::Px(10.0),
-                    height: Val::Px(50.0),
+                    height: Val::Px(115.0),
                     width: Val::Percent(100.0),
                     align_items: AlignItems::Center,
                     flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(5.0),
 
                     ..Default::default()
                 },
@@ -980,58 +5227,101 @@ fn setup_ui(mut commands: Commands, main_camera: Query<Entity, With<MainCamera>>
             },
         ))
         .with_children(|parent| {
-            // Health background
-            parent
-                .spawn((
-                    NodeBundle {
-                        background_color: BackgroundColor(Color::Rgba {
-                            red: 0.3,
-                            green: 0.3,
-                            blue: 0.3,
-                            alpha: 1.0,
-                        }),
-                        style: Style {
-                            height: Val::Px(15.0),
-                            width: Val::Px(100.0),
-                            justify_content: JustifyContent::Center,
-                            ..Default::default()
-                        },
+            parent.spawn((
+                UiLives,
+                TextBundle::from_section(
+                    format!("Lives: {STARTING_LIVES}"),
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::WHITE,
                         ..Default::default()
                     },
-                    UiHealthBack,
-                ))
-                .with_children(|parent| {
-                    // Health foreground
-                    parent.spawn((
-                        NodeBundle {
-                            background_color: BackgroundColor(Color::Rgba {
-                                red: 1.0,
-                                green: 0.0,
-                                blue: 0.0,
-                                alpha: 1.0,
-                            }),
-                            style: Style {
-                                width: Val::Percent(50.0),
-                                height: Val::Percent(100.0),
-                                ..Default::default()
-                            },
-                            ..Default::default()
-                        },
-                        UiHealthFront,
-                    ));
-                });
+                ),
+            ));
+            spawn_ui_bar(parent, UiBar::ChargeBack, UiBar::ChargeFront, Color::CYAN);
+            spawn_ui_bar(
+                parent,
+                UiBar::ShieldBack,
+                UiBar::ShieldFront,
+                Color::rgb(0.3, 0.6, 1.0),
+            );
+            spawn_ui_bar(
+                parent,
+                UiBar::HealthBack,
+                UiBar::HealthFront,
+                Color::Rgba {
+                    red: 1.0,
+                    green: 0.0,
+                    blue: 0.0,
+                    alpha: 1.0,
+                },
+            );
+            spawn_ui_bar(parent, UiBar::HeatBack, UiBar::HeatFront, Color::ORANGE);
+        });
+    commands
+        .spawn((
+            TargetCamera(main_camera.single()),
+            BossBar,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(10.0),
+                    width: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(5.0),
+                    ..Default::default()
+                },
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "BOSS",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::WHITE,
+                    ..Default::default()
+                },
+            ));
+            spawn_ui_bar(parent, UiBar::BossBack, UiBar::BossFront, Color::PURPLE);
         });
 }
 
 fn update_player_ui(
-    mut health_back: Query<&mut Style, (With<UiHealthBack>, Without<UiHealthFront>)>,
-    mut health_front: Query<&mut Style, (With<UiHealthFront>, Without<UiHealthBack>)>,
-    player_stats: Query<&Health, With<Player>>,
+    mut bars: Query<(&mut Style, &mut BackgroundColor, &UiBar)>,
+    player_stats: Query<(&Health, Option<&Shield>, Option<&Weapon>), With<Player>>,
 ) {
-    // more than one ui is a yike
-    let stats = player_stats.single();
-    health_back.single_mut().width = Val::Px(stats.max);
-    health_front.single_mut().width = Val::Px(stats.health);
+    // more than one ui is a yike. Bars keep their last values while respawning
+    let Ok((stats, shield, weapon)) = player_stats.get_single() else {
+        return;
+    };
+    for (mut style, mut color, bar) in bars.iter_mut() {
+        style.width = Val::Px(match bar {
+            UiBar::HealthBack => stats.max,
+            UiBar::HealthFront => stats.health,
+            UiBar::ShieldBack => shield.map_or(0.0, |shield| shield.capacity),
+            UiBar::ShieldFront => shield.map_or(0.0, |shield| shield.current),
+            // The charge meter only shows up for weapons that charge
+            UiBar::ChargeBack => weapon
+                .filter(|weapon| weapon.charge_time > 0.0)
+                .map_or(0.0, |_| 100.0),
+            UiBar::ChargeFront => weapon.map_or(0.0, |weapon| weapon.charge * 100.0),
+            UiBar::HeatBack => weapon.map_or(0.0, |weapon| weapon.heat.capacity),
+            UiBar::HeatFront => weapon.map_or(0.0, |weapon| weapon.heat.current),
+            // Handled by update_boss_bar
+            UiBar::BossBack | UiBar::BossFront => continue,
+        });
+        if let UiBar::HeatFront = bar {
+            // Goes red while locked out
+            color.0 = if weapon.map_or(false, |weapon| weapon.heat.overheated > 0.0) {
+                Color::RED
+            } else {
+                Color::ORANGE
+            };
+        }
+    }
 }
 #[derive(Resource)]
 struct UiAnimationTimer(Timer);
@@ -1071,6 +5361,7 @@ fn setup_tutorials(
             TutorialRoot,
             Lifetime {
                 time: Timer::from_seconds(20.0, TimerMode::Once),
+                on_expire: Expire::DespawnRecursive,
             },
             NodeBundle {
                 style: Style {
@@ -1105,6 +5396,7 @@ fn setup_tutorials(
                     TutorialBackground,
                     Lifetime {
                         time: Timer::from_seconds(20.0, TimerMode::Once),
+                        on_expire: Expire::Nothing,
                     },
                 ))
                 .with_children(|parent| {
@@ -1122,6 +5414,7 @@ fn setup_tutorials(
                                 Tutorial,
                                 Lifetime {
                                     time: Timer::from_seconds(20.0, TimerMode::Once),
+                                    on_expire: Expire::Nothing,
                                 },
                                 ImageBundle {
                                     image: UiImage::new(assets.load("rotate_icon_100.png")),
@@ -1136,6 +5429,7 @@ fn setup_tutorials(
                                 Tutorial,
                                 Lifetime {
                                     time: Timer::from_seconds(20.0, TimerMode::Once),
+                                    on_expire: Expire::Nothing,
                                 },
                                 Animatable {
                                     current_frame: 0,
@@ -1174,6 +5468,7 @@ fn setup_tutorials(
                                 Tutorial,
                                 Lifetime {
                                     time: Timer::from_seconds(20.0, TimerMode::Once),
+                                    on_expire: Expire::Nothing,
                                 },
                                 ImageBundle {
                                     image: UiImage::new(assets.load("shoot_icon_100.png")),
@@ -1188,6 +5483,7 @@ fn setup_tutorials(
                                 Tutorial,
                                 Lifetime {
                                     time: Timer::from_seconds(20.0, TimerMode::Once),
+                                    on_expire: Expire::Nothing,
                                 },
                                 Animatable {
                                     current_frame: 0,
@@ -1226,6 +5522,7 @@ fn setup_tutorials(
                                 Tutorial,
                                 Lifetime {
                                     time: Timer::from_seconds(20.0, TimerMode::Once),
+                                    on_expire: Expire::Nothing,
                                 },
                                 ImageBundle {
                                     image: UiImage::new(assets.load("exit.png")),
@@ -1241,6 +5538,7 @@ fn setup_tutorials(
                                     Tutorial,
                                     Lifetime {
                                         time: Timer::from_seconds(20.0, TimerMode::Once),
+                                        on_expire: Expire::Nothing,
                                     },
                                     Animatable {
                                         current_frame: 0,
@@ -1267,6 +5565,7 @@ fn setup_tutorials(
                                     Tutorial,
                                     Lifetime {
                                         time: Timer::from_seconds(20.0, TimerMode::Once),
+                                        on_expire: Expire::Nothing,
                                     },
                                     Animatable {
                                         current_frame: 0,
@@ -1306,6 +5605,7 @@ fn setup_tutorials(
                                 Tutorial,
                                 Lifetime {
                                     time: Timer::from_seconds(20.0, TimerMode::Once),
+                                    on_expire: Expire::Nothing,
                                 },
                                 ImageBundle {
                                     image: UiImage::new(assets.load("thrust_icon_100.png")),
@@ -1320,6 +5620,7 @@ fn setup_tutorials(
                                 Tutorial,
                                 Lifetime {
                                     time: Timer::from_seconds(20.0, TimerMode::Once),
+                                    on_expire: Expire::Nothing,
                                 },
                                 Animatable {
                                     current_frame: 0,
@@ -1374,12 +5675,10 @@ fn animate(
 }
 
 fn fade_tutorials(
-    mut commands: Commands,
     mut tutorials: Query<
         (&Lifetime, &mut BackgroundColor),
         (With<Tutorial>, Without<TutorialBackground>),
     >,
-    mut tutorial_roots: Query<(Entity, &Lifetime), With<TutorialRoot>>,
     mut tutorial_backgrounds: Query<(&Lifetime, &mut BackgroundColor), With<TutorialBackground>>,
 ) {
     for (lifetime, mut background) in tutorials.iter_mut() {
@@ -1392,9 +5691,4 @@ fn fade_tutorials(
             .0
             .set_a(lifetime.time.remaining_secs().min(10.0) / 20.0);
     }
-    for (entity, lifetime) in tutorial_roots.iter_mut() {
-        if lifetime.time.finished() {
-            commands.entity(entity).despawn_recursive();
-        }
-    }
 }